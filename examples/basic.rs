@@ -11,8 +11,8 @@ use ggez::conf::{WindowMode, WindowSetup};
 use ggez::graphics::{self, DrawMode, Point2};
 use ggez::event::{run, Axis, Button, EventHandler, Keycode};
 use ggez::timer;
-use ggez_inputty::{InputHandler, InputHandlerDefGen, InputtyResult, PhysicalInput as PI,
-                   PhysicalInputValue as PIV};
+use ggez_inputty::{InputHandler, InputHandlerDefGen, InputtyError, InputtyResult,
+                   PhysicalInput as PI, PhysicalInputValue as PIV};
 use ggez_inputty::virtual_axis::{self, VirtualAxisInput, VirtualAxisPhase, VirtualAxisState};
 
 #[derive(Hash, PartialEq, Eq, Clone, Debug)]
@@ -23,15 +23,13 @@ enum Input {
 }
 
 struct InputState {
-    should_exit: bool,
     spin_axis: VirtualAxisState,
 }
 
 impl InputState {
     fn new() -> Self {
         InputState {
-            should_exit: false,
-            spin_axis: VirtualAxisState::new(0.1, 0.2, 0.1),
+            spin_axis: VirtualAxisState::from_durations(0.15, 0.1, 0.15),
         }
     }
 }
@@ -49,27 +47,30 @@ impl App {
         let mut input_handler = InputHandler::<Input, InputState>::new();
         define_virtual_axis!(input_handler, Input::Spin, spin_axis);
         input_handler
-            .define(Input::Exit, |_state, _physical, _value| -> InputtyResult {
-                info!(
-                    "Logical input 'Exit' triggered via {:?}: {:?}",
-                    _physical, _value
-                );
-                _state.should_exit = true;
-                Ok(())
-            })
+            .define_with_context(
+                Input::Exit,
+                |_ctx, _state, _physical, _value| -> InputtyResult {
+                    info!(
+                        "Logical input 'Exit' triggered via {:?}: {:?}",
+                        _physical, _value
+                    );
+                    _ctx.quit()
+                        .map_err(|error| InputtyError::with_source("Couldn't quit", error))
+                },
+            )
             .define(
                 Input::ReturnError,
                 |_state, _physical, _value| -> InputtyResult {
                     if let PIV::Button(true) = _value {
                         info!("Propagating an error");
-                        Err("Oh no!")
+                        Err("Oh no!".into())
                     } else {
                         Ok(())
                     }
                 },
             )
-            .bind(PI::Key(Keycode::Escape, true), Input::Exit)
-            .bind(PI::Key(Keycode::E, false), Input::ReturnError)
+            .bind(PI::Key(Keycode::Escape), Input::Exit)
+            .bind(PI::Key(Keycode::E), Input::ReturnError)
             .bind(PI::CButton(0, Button::Back), Input::Exit)
             .bind(
                 PI::CAxis(0, Axis::LeftX),
@@ -84,11 +85,11 @@ impl App {
                 Input::Spin(VirtualAxisInput::Positive),
             )
             .bind(
-                PI::Key(Keycode::Left, false),
+                PI::Key(Keycode::Left),
                 Input::Spin(VirtualAxisInput::Negative),
             )
             .bind(
-                PI::Key(Keycode::Right, false),
+                PI::Key(Keycode::Right),
                 Input::Spin(VirtualAxisInput::Positive),
             );
 
@@ -121,10 +122,7 @@ impl EventHandler for App {
         while timer::check_update_time(ctx, DESIRED_UPS) {
             self.rotation_angle += self.input_state.spin_axis.value() / 10.0;
             let mut f = 0.0;
-            self.input_state.spin_axis.update(1.0);
-            if self.input_state.should_exit {
-                ctx.quit()?;
-            }
+            self.input_state.spin_axis.update(1.0 / DESIRED_UPS as f32);
         }
         Ok(())
     }
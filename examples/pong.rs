@@ -13,15 +13,17 @@ use ggez::graphics::{self, Point2};
 use ggez::event::{run, Axis, Button, EventHandler, Keycode};
 use ggez::nalgebra;
 use ggez::timer;
-use ggez_inputty::{InputHandler, InputtyResult, PhysicalInput as PI, PhysicalInputValue as PIV};
-use ggez_inputty::virtual_axis::{self, VirtualAxisPhase};
+use ggez_inputty::{InputHandler, InputtyResult, PhysicalInput as PI};
+use ggez_inputty::virtual_axis::VirtualAxisState;
 
+const DESIRED_UPS: u32 = 60;
 const BALL_DIM: f32 = 0.005;
 const BALL_MAX_VELOCITY: f32 = 0.015;
 const FIELD_DIM: (f32, f32) = (1.5, 1.0);
 const PADDLE_DIM: (f32, f32) = (0.01, 0.10);
 const PADDLE_PAD: f32 = 0.05;
 const PADDLE_MAX_VELOCITY: f32 = 0.02;
+const STICK_DEAD_ZONE: f32 = 0.15;
 
 #[derive(Hash, PartialEq, Eq, Clone, Debug)]
 enum Input {
@@ -33,22 +35,21 @@ enum Input {
 }
 
 struct PlayerInputState {
-    axis: f32,
-    axis_state: VirtualAxisPhase,
+    axis: VirtualAxisState,
     pause_timer: u32,
 }
 
 impl PlayerInputState {
     fn new() -> Self {
         PlayerInputState {
-            axis: 0.0,
-            axis_state: VirtualAxisPhase::Relax,
+            axis: VirtualAxisState::from_durations(1.0 / 6.0, 1.0 / 12.0, 1.0 / 6.0)
+                .with_dead_zone(STICK_DEAD_ZONE, 1.0),
             pause_timer: 0,
         }
     }
 
     fn update(&mut self) {
-        virtual_axis::axis_update(&mut self.axis, &self.axis_state, 0.1, 0.2, 0.1);
+        self.axis.update(1.0 / DESIRED_UPS as f32);
         if self.pause_timer > 0 {
             self.pause_timer -= 1;
         }
@@ -78,53 +79,45 @@ impl InputState {
             .define(
                 Input::PaddleAnalog(0),
                 |_state, _physical, _value| -> InputtyResult {
-                    if let PIV::Axis(raw_axis) = _value {
-                        _state.paddle_l.axis_state = VirtualAxisPhase::Ignore;
-                        _state.paddle_l.axis = raw_axis as f32 / i16::max_value() as f32;
-                    }
-                    Ok(())
+                    _state.paddle_l.axis.input_analog(_value)
                 },
             )
             .define(
                 Input::PaddleUp(0),
                 |_state, _physical, _value| -> InputtyResult {
-                    virtual_axis::axis_input_neg(&mut _state.paddle_l.axis_state, _value)
+                    _state.paddle_l.axis.input_neg(_value)
                 },
             )
             .define(
                 Input::PaddleDown(0),
                 |_state, _physical, _value| -> InputtyResult {
-                    virtual_axis::axis_input_pos(&mut _state.paddle_l.axis_state, _value)
+                    _state.paddle_l.axis.input_pos(_value)
                 },
             )
             .define(
                 Input::PaddleAnalog(1),
                 |_state, _physical, _value| -> InputtyResult {
-                    if let PIV::Axis(raw_axis) = _value {
-                        _state.paddle_r.axis_state = VirtualAxisPhase::Ignore;
-                        _state.paddle_r.axis = raw_axis as f32 / i16::max_value() as f32;
-                    }
-                    Ok(())
+                    _state.paddle_r.axis.input_analog(_value)
                 },
             )
             .define(
                 Input::PaddleUp(1),
                 |_state, _physical, _value| -> InputtyResult {
-                    virtual_axis::axis_input_neg(&mut _state.paddle_r.axis_state, _value)
+                    _state.paddle_r.axis.input_neg(_value)
                 },
             )
             .define(
                 Input::PaddleDown(1),
                 |_state, _physical, _value| -> InputtyResult {
-                    virtual_axis::axis_input_pos(&mut _state.paddle_r.axis_state, _value)
+                    _state.paddle_r.axis.input_pos(_value)
                 },
             )
             .bind(PI::CAxis(0, Axis::LeftY), Input::PaddleAnalog(0))
-            .bind(PI::Key(Keycode::W, false), Input::PaddleUp(0))
-            .bind(PI::Key(Keycode::S, false), Input::PaddleDown(0))
+            .bind(PI::Key(Keycode::W), Input::PaddleUp(0))
+            .bind(PI::Key(Keycode::S), Input::PaddleDown(0))
             .bind(PI::CAxis(1, Axis::LeftY), Input::PaddleAnalog(1))
-            .bind(PI::Key(Keycode::Up, false), Input::PaddleUp(1))
-            .bind(PI::Key(Keycode::Down, false), Input::PaddleDown(1))
+            .bind(PI::Key(Keycode::Up), Input::PaddleUp(1))
+            .bind(PI::Key(Keycode::Down), Input::PaddleDown(1))
     }
 }
 
@@ -212,7 +205,7 @@ impl GameState {
     }
 
     fn update(&mut self, input_state: &InputState) {
-        let paddle_l_vel = PADDLE_MAX_VELOCITY * input_state.paddle_l.axis;
+        let paddle_l_vel = PADDLE_MAX_VELOCITY * input_state.paddle_l.axis.value();
         self.paddle_l_pos += paddle_l_vel;
         self.paddle_l_pos = nalgebra::clamp(
             self.paddle_l_pos,
@@ -220,7 +213,7 @@ impl GameState {
             FIELD_DIM.1 - PADDLE_DIM.1 / 2.0,
         );
 
-        let paddle_r_vel = PADDLE_MAX_VELOCITY * input_state.paddle_r.axis;
+        let paddle_r_vel = PADDLE_MAX_VELOCITY * input_state.paddle_r.axis.value();
         self.paddle_r_pos += paddle_r_vel;
         self.paddle_r_pos = nalgebra::clamp(
             self.paddle_r_pos,
@@ -309,7 +302,6 @@ impl App {
 
 impl EventHandler for App {
     fn update(&mut self, ctx: &mut Context) -> GameResult<()> {
-        const DESIRED_UPS: u32 = 60;
         while timer::check_update_time(ctx, DESIRED_UPS) {
             self.input_state.update();
             self.game_state.update(&self.input_state);
@@ -0,0 +1,100 @@
+use ggez::event::Keycode;
+use input_handler::{PhysicalInput, PhysicalInputValue};
+
+/// Kinds of devices physical inputs can come from, as far as capturing is concerned.
+#[derive(Hash, PartialEq, Eq, Copy, Clone, Debug)]
+pub enum DeviceKind {
+    Key,
+    MouseButton,
+    MouseWheel,
+    ControllerButton,
+    ControllerAxis,
+    /// Halves of controller axes, as pressed past thresholds.
+    ControllerAxisButton,
+}
+
+impl DeviceKind {
+    /// Kind of device given physical input comes from, if it can be captured at all.
+    pub fn of(physical: &PhysicalInput) -> Option<DeviceKind> {
+        match *physical {
+            PhysicalInput::Key(..) => Some(DeviceKind::Key),
+            PhysicalInput::MButton(..) => Some(DeviceKind::MouseButton),
+            PhysicalInput::MWheelX(..) | PhysicalInput::MWheelY(..) => Some(DeviceKind::MouseWheel),
+            PhysicalInput::CButton(..) => Some(DeviceKind::ControllerButton),
+            PhysicalInput::CAxis(..) => Some(DeviceKind::ControllerAxis),
+            PhysicalInput::CAxisButton(..) => Some(DeviceKind::ControllerAxisButton),
+            PhysicalInput::MMotion => None,
+        }
+    }
+}
+
+/// Configures what `InputHandler::begin_capture_with()` accepts.
+#[derive(Clone, Debug)]
+pub struct CaptureOptions {
+    /// Device kinds that can be captured.
+    pub devices: Vec<DeviceKind>,
+    /// Physical inputs that cancel the capture instead of being captured.
+    pub cancel: Vec<PhysicalInput>,
+    /// Absolute raw value an axis has to reach to be captured.
+    pub axis_threshold: i16,
+}
+
+impl Default for CaptureOptions {
+    fn default() -> Self {
+        CaptureOptions {
+            devices: vec![
+                DeviceKind::Key,
+                DeviceKind::MouseButton,
+                DeviceKind::MouseWheel,
+                DeviceKind::ControllerButton,
+                DeviceKind::ControllerAxis,
+            ],
+            cancel: vec![PhysicalInput::Key(Keycode::Escape)],
+            axis_threshold: i16::max_value() / 2,
+        }
+    }
+}
+
+/// Outcome of a capture, as returned by `InputHandler::take_capture_result()`.
+#[derive(PartialEq, Clone, Debug)]
+pub enum CaptureResult<LogicalInput> {
+    /// Physical input was captured and bound to the logical input.
+    Bound(LogicalInput, PhysicalInput),
+    /// Capture of the logical input was cancelled.
+    Cancelled(LogicalInput),
+}
+
+pub(crate) struct Capture<LogicalInput> {
+    pub logical: LogicalInput,
+    pub options: CaptureOptions,
+}
+
+impl<LogicalInput: Clone> Capture<LogicalInput> {
+    /// Decides what given event means for the capture; `None` if it should keep waiting.
+    pub fn process(
+        &self,
+        physical: PhysicalInput,
+        value: PhysicalInputValue,
+    ) -> Option<CaptureResult<LogicalInput>> {
+        let qualifies = match value {
+            PhysicalInputValue::Button(down) => down,
+            PhysicalInputValue::Axis(raw_axis) => {
+                (raw_axis as i32).abs() >= self.options.axis_threshold as i32
+            }
+            PhysicalInputValue::Wheel(delta) => delta != 0,
+            PhysicalInputValue::Repeat | PhysicalInputValue::XY(..) => false,
+        };
+        if !qualifies {
+            return None;
+        }
+        if self.options.cancel.contains(&physical) {
+            return Some(CaptureResult::Cancelled(self.logical.clone()));
+        }
+        match DeviceKind::of(&physical) {
+            Some(kind) if self.options.devices.contains(&kind) => {
+                Some(CaptureResult::Bound(self.logical.clone(), physical))
+            }
+            _ => None,
+        }
+    }
+}
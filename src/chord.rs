@@ -0,0 +1,51 @@
+use input_handler::{PhysicalInput, PhysicalInputValue};
+
+/// A logical input bound to several physical inputs held together.
+pub(crate) struct Chord<LogicalInput> {
+    pub members: Vec<PhysicalInput>,
+    pub logical: LogicalInput,
+    /// Whether members' own bindings are suppressed while the chord is active.
+    pub suppress: bool,
+    pub active: bool,
+}
+
+impl<LogicalInput> Chord<LogicalInput> {
+    /// Tracks given physical input event; returns `Some(true)` if the chord was just pressed,
+    /// `Some(false)` if it was just released, and `None` otherwise.
+    pub fn update<F>(
+        &mut self,
+        physical: PhysicalInput,
+        value: PhysicalInputValue,
+        is_held: F,
+    ) -> Option<bool>
+    where
+        F: Fn(&PhysicalInput) -> bool,
+    {
+        if !self.members.contains(&physical) {
+            return None;
+        }
+        match value {
+            PhysicalInputValue::Button(true) if !self.active => {
+                if self
+                    .members
+                    .iter()
+                    .all(|member| *member == physical || is_held(member))
+                {
+                    self.active = true;
+                    return Some(true);
+                }
+                None
+            }
+            PhysicalInputValue::Button(false) if self.active => {
+                self.active = false;
+                Some(false)
+            }
+            _ => None,
+        }
+    }
+
+    /// Checks if given physical input's own bindings should be suppressed.
+    pub fn suppresses(&self, physical: &PhysicalInput) -> bool {
+        self.active && self.suppress && self.members.contains(physical)
+    }
+}
@@ -0,0 +1,216 @@
+use ggez::event::Mod;
+use input_handler::PhysicalInput;
+use options::BindingOptions;
+use std::collections::HashMap;
+use std::fmt::Debug;
+use std::hash::Hash;
+
+/// A mapping from physical inputs to logical inputs, along with its reverse.
+///
+/// `InputHandler` has a base binding context that is always active, and any number of named
+/// ones that can be pushed on top of it.
+#[derive(Clone, Debug)]
+pub struct BindingContext<LogicalInput>
+where
+    LogicalInput: Hash + Eq + Clone + Debug,
+{
+    bindings: HashMap<PhysicalInput, Vec<(LogicalInput, BindingOptions)>>,
+    /// Keys of `bindings`, in order of their first binding.
+    physicals: Vec<PhysicalInput>,
+    /// Reverse of `bindings`, kept in sync with it.
+    reverse_bindings: HashMap<LogicalInput, Vec<PhysicalInput>>,
+    fallthrough: bool,
+}
+
+impl<LogicalInput> BindingContext<LogicalInput>
+where
+    LogicalInput: Hash + Eq + Clone + Debug,
+{
+    /// Creates an empty context; physical inputs it has no bindings for will be passed on to
+    /// lower contexts if `fallthrough` is set, and blocked otherwise.
+    pub fn new(fallthrough: bool) -> Self {
+        BindingContext {
+            bindings: HashMap::new(),
+            physicals: Vec::new(),
+            reverse_bindings: HashMap::new(),
+            fallthrough,
+        }
+    }
+
+    pub fn fallthrough(&self) -> bool {
+        self.fallthrough
+    }
+
+    pub fn set_fallthrough(&mut self, fallthrough: bool) -> &mut Self {
+        self.fallthrough = fallthrough;
+        self
+    }
+
+    pub fn bind(&mut self, physical: PhysicalInput, logical: LogicalInput) -> &mut Self {
+        self.bind_with(physical, logical, BindingOptions::default())
+    }
+
+    /// Binds given physical and logical inputs; if they already are, only replaces the options.
+    pub fn bind_with(
+        &mut self,
+        physical: PhysicalInput,
+        logical: LogicalInput,
+        options: BindingOptions,
+    ) -> &mut Self {
+        if let Some(logicals) = self.bindings.get_mut(&physical) {
+            if let Some(bound) = logicals
+                .iter_mut()
+                .find(|&&mut (ref bound, _)| *bound == logical)
+            {
+                bound.1 = options;
+                return self;
+            }
+        }
+        self.reverse_bindings
+            .entry(logical.clone())
+            .or_insert_with(Vec::new)
+            .push(physical);
+        if !self.bindings.contains_key(&physical) {
+            self.physicals.push(physical);
+        }
+        self.bindings
+            .entry(physical)
+            .or_insert_with(Vec::new)
+            .push((logical, options));
+        self
+    }
+
+    /// Removes the binding between given physical and logical inputs, if any.
+    pub fn unbind(&mut self, physical: PhysicalInput, logical: &LogicalInput) -> &mut Self {
+        let mut empty = false;
+        if let Some(logicals) = self.bindings.get_mut(&physical) {
+            logicals.retain(|&(ref bound, _)| bound != logical);
+            empty = logicals.is_empty();
+        }
+        if empty {
+            self.bindings.remove(&physical);
+            self.physicals.retain(|bound| *bound != physical);
+        }
+        let mut empty = false;
+        if let Some(physicals) = self.reverse_bindings.get_mut(logical) {
+            physicals.retain(|bound| *bound != physical);
+            empty = physicals.is_empty();
+        }
+        if empty {
+            self.reverse_bindings.remove(logical);
+        }
+        self
+    }
+
+    /// Removes every binding of given logical input.
+    pub fn unbind_all(&mut self, logical: &LogicalInput) -> &mut Self {
+        if let Some(physicals) = self.reverse_bindings.remove(logical) {
+            for physical in physicals {
+                let mut empty = false;
+                if let Some(logicals) = self.bindings.get_mut(&physical) {
+                    logicals.retain(|&(ref bound, _)| bound != logical);
+                    empty = logicals.is_empty();
+                }
+                if empty {
+                    self.bindings.remove(&physical);
+                    self.physicals.retain(|bound| *bound != physical);
+                }
+            }
+        }
+        self
+    }
+
+    /// Replaces the `old` physical input of given logical input with the `new` one, keeping
+    /// binding options; does nothing if `old` isn't bound to it.
+    pub fn rebind(
+        &mut self,
+        logical: LogicalInput,
+        old: PhysicalInput,
+        new: PhysicalInput,
+    ) -> &mut Self {
+        if let Some(options) = self.options_for(old, &logical).cloned() {
+            self.unbind(old, &logical);
+            self.bind_with(new, logical, options);
+        }
+        self
+    }
+
+    /// Options of the binding between given physical and logical inputs, if there is one.
+    pub fn options_for(
+        &self,
+        physical: PhysicalInput,
+        logical: &LogicalInput,
+    ) -> Option<&BindingOptions> {
+        self.bindings.get(&physical).and_then(|logicals| {
+            logicals
+                .iter()
+                .find(|&&(ref bound, _)| bound == logical)
+                .map(|&(_, ref options)| options)
+        })
+    }
+
+    /// Every binding, as physical input, logical input and binding options; grouped by physical
+    /// input, in order of binding.
+    pub fn bindings<'a>(
+        &'a self,
+    ) -> impl Iterator<Item = (PhysicalInput, &'a LogicalInput, &'a BindingOptions)> + 'a {
+        let bindings = &self.bindings;
+        self.physicals.iter().flat_map(move |physical| {
+            bindings[physical]
+                .iter()
+                .map(move |&(ref logical, ref options)| (*physical, logical, options))
+        })
+    }
+
+    /// Removes every binding.
+    pub fn clear_bindings(&mut self) -> &mut Self {
+        self.bindings.clear();
+        self.physicals.clear();
+        self.reverse_bindings.clear();
+        self
+    }
+
+    /// Physical inputs currently bound to given logical input.
+    pub fn bindings_for<'a>(
+        &'a self,
+        logical: &LogicalInput,
+    ) -> impl Iterator<Item = PhysicalInput> + 'a {
+        self.reverse_bindings
+            .get(logical)
+            .into_iter()
+            .flat_map(|physicals| physicals.iter().cloned())
+    }
+
+    /// Logical inputs currently bound to given physical input.
+    pub fn logicals_for<'a>(
+        &'a self,
+        physical: &PhysicalInput,
+    ) -> impl Iterator<Item = &'a LogicalInput> + 'a {
+        self.bindings
+            .get(physical)
+            .into_iter()
+            .flat_map(|logicals| logicals.iter().map(|&(ref logical, _)| logical))
+    }
+
+    /// Bindings of given physical input whose options match held modifier keys; empty if there
+    /// are none.
+    pub(crate) fn resolve(
+        &self,
+        physical: &PhysicalInput,
+        keymod: Mod,
+    ) -> Vec<(LogicalInput, BindingOptions)> {
+        let is_key = match *physical {
+            PhysicalInput::Key(..) => true,
+            _ => false,
+        };
+        self.bindings
+            .get(physical)
+            .into_iter()
+            .flat_map(|logicals| logicals.iter())
+            .filter(|&&(_, ref options)| {
+                !is_key || options.modifiers.matches(keymod, options.distinguish_sides)
+            })
+            .cloned()
+            .collect()
+    }
+}
@@ -0,0 +1,115 @@
+use input_handler::{PhysicalInput, PhysicalInputValue};
+use std::error::Error;
+use std::fmt;
+
+/// Error returned by a logical input callback.
+#[derive(Debug)]
+pub struct InputtyError {
+    message: String,
+    source: Option<Box<Error>>,
+}
+
+impl InputtyError {
+    pub fn new<S: Into<String>>(message: S) -> Self {
+        InputtyError {
+            message: message.into(),
+            source: None,
+        }
+    }
+
+    /// Creates an error caused by another one.
+    pub fn with_source<S, E>(message: S, source: E) -> Self
+    where
+        S: Into<String>,
+        E: Error + 'static,
+    {
+        InputtyError {
+            message: message.into(),
+            source: Some(Box::new(source)),
+        }
+    }
+
+    pub fn message(&self) -> &str {
+        &self.message
+    }
+}
+
+impl fmt::Display for InputtyError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self.source {
+            Some(ref source) => write!(f, "{}: {}", self.message, source),
+            None => write!(f, "{}", self.message),
+        }
+    }
+}
+
+impl Error for InputtyError {
+    fn source(&self) -> Option<&(Error + 'static)> {
+        self.source.as_ref().map(|source| &**source)
+    }
+}
+
+impl From<&'static str> for InputtyError {
+    fn from(message: &'static str) -> Self {
+        InputtyError::new(message)
+    }
+}
+
+impl From<String> for InputtyError {
+    fn from(message: String) -> Self {
+        InputtyError::new(message)
+    }
+}
+
+/// An error returned by a logical input callback, along with what it was called with.
+#[derive(Debug)]
+pub struct CallbackError<LogicalInput> {
+    pub logical: LogicalInput,
+    pub physical: PhysicalInput,
+    pub value: PhysicalInputValue,
+    pub error: InputtyError,
+}
+
+impl<LogicalInput: fmt::Debug> fmt::Display for CallbackError<LogicalInput> {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(
+            f,
+            "Logical input callback {:?} ( {:?}, {:?} ) returned an error: {}",
+            self.logical, self.physical, self.value, self.error
+        )
+    }
+}
+
+impl<LogicalInput: fmt::Debug> Error for CallbackError<LogicalInput> {
+    fn source(&self) -> Option<&(Error + 'static)> {
+        Some(&self.error)
+    }
+}
+
+/// Result of dispatching a physical input event; only fails under `ErrorPolicy::Abort`.
+pub type DispatchResult<LogicalInput> = Result<(), CallbackError<LogicalInput>>;
+
+/// Logs the error dispatching failed with, if any; used where it can't be returned.
+pub fn log_dispatch_error<LogicalInput: fmt::Debug>(result: DispatchResult<LogicalInput>) {
+    if let Err(error) = result {
+        error!("Dispatching aborted: {}", error);
+    }
+}
+
+/// What `InputHandler` does with errors returned by logical input callbacks.
+pub enum ErrorPolicy<LogicalInput> {
+    /// Logs them with `error!`.
+    Log,
+    /// Keeps them until taken with `InputHandler::drain_errors()`.
+    Queue,
+    /// Passes them to given closure.
+    Hook(Box<FnMut(CallbackError<LogicalInput>)>),
+    /// Stops dispatching the event, and returns the error from the event method.
+    Abort,
+}
+
+impl<LogicalInput> Default for ErrorPolicy<LogicalInput> {
+    fn default() -> Self {
+        ErrorPolicy::Log
+    }
+}
@@ -0,0 +1,30 @@
+use input_handler::{PhysicalInput, PhysicalInputValue};
+
+/// A logical input receiving a value, as queued for `InputHandler::drain_events()`.
+#[derive(PartialEq, Clone, Debug)]
+pub struct LogicalEvent<LogicalInput> {
+    pub logical: LogicalInput,
+    /// Physical input the event came from.
+    pub physical: PhysicalInput,
+    pub value: PhysicalInputValue,
+    /// Time of the handler when the event was dispatched, in seconds.
+    pub timestamp: f32,
+}
+
+/// How `InputHandler` delivers values to logical inputs.
+#[derive(PartialEq, Eq, Copy, Clone, Debug)]
+pub enum DispatchMode {
+    /// Callbacks are invoked right away, from within event methods.
+    Immediate,
+    /// Events are queued until drained; callbacks aren't invoked, and logical inputs don't
+    /// need to be defined.
+    Queued,
+    /// Callbacks are invoked and events are queued.
+    Both,
+}
+
+impl Default for DispatchMode {
+    fn default() -> Self {
+        DispatchMode::Immediate
+    }
+}
@@ -1,118 +1,1168 @@
+use capture::{Capture, CaptureOptions, CaptureResult};
+use chord::Chord;
+use context::BindingContext;
+use error::{CallbackError, DispatchResult, ErrorPolicy, InputtyError};
+use event::{DispatchMode, LogicalEvent};
 use ggez::event::{Axis, Button, Keycode, Mod, MouseButton, MouseState};
+use ggez::Context;
+use interaction::{Interaction, InteractionState};
+use logical_state::LogicalInputState;
+use options::{AxisThreshold, BindingOptions, WheelMode};
+use recording::{RecordedEvent, Recorder, Recording};
+use sequence::{Sequence, SequenceOptions, SequenceStep};
+use std::cmp::Reverse;
+use std::collections::{HashMap, HashSet};
 use std::fmt::Debug;
 use std::hash::Hash;
-use std::collections::HashMap;
+use std::vec::Drain;
 
 /// Gathers kinds of physical (read: SDL2-specific) sources of input under a single enum.
 #[derive(Hash, Eq, PartialEq, Copy, Clone, Debug)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
 pub enum PhysicalInput {
     // TODO: Look at joysticks, etc.
     /// Instance ID, axis.
-    CAxis(i32, Axis),
+    CAxis(
+        i32,
+        #[cfg_attr(feature = "serde", serde(with = "::profile::axis"))] Axis,
+    ),
+    /// Instance ID, axis, positive/negative half; pressed and released as the axis crosses
+    /// thresholds set with `InputHandler::set_axis_threshold()`.
+    CAxisButton(
+        i32,
+        #[cfg_attr(feature = "serde", serde(with = "::profile::axis"))] Axis,
+        bool,
+    ),
     /// Instante ID, button.
-    CButton(i32, Button),
-    MButton(MouseButton),
+    CButton(
+        i32,
+        #[cfg_attr(feature = "serde", serde(with = "::profile::button"))] Button,
+    ),
+    MButton(#[cfg_attr(feature = "serde", serde(with = "::profile::mouse_button"))] MouseButton),
     /// Positive/negative.
     MWheelX(bool),
     /// Positive/negative.
     MWheelY(bool),
     MMotion,
-    /// Keycode, repeated.
-    Key(Keycode, bool),
+    /// Keycode; repeats are told apart by `PhysicalInputValue::Repeat`.
+    Key(#[cfg_attr(feature = "serde", serde(with = "::profile::keycode"))] Keycode),
 }
 
 /// Facilitates passing concrete values to parsing callbacks; types are as used in SDL2.
 #[derive(Hash, Eq, PartialEq, Copy, Clone, Debug)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
 pub enum PhysicalInputValue {
     // TODO: Look at joysticks, etc.
     /// Raw value.
     Axis(i16),
     /// Down/up.
     Button(bool),
+    /// Key is still down and was repeated.
+    Repeat,
     /// X, Y, relative X, relative Y.
     XY(i32, i32, i32, i32),
+    /// Signed amount the wheel was scrolled by; see `WheelMode`.
+    Wheel(i32),
 }
 
-type LogicalInputCallback<State> =
-    Fn(&mut State, PhysicalInput, PhysicalInputValue) -> InputtyResult;
-pub type InputtyResult = Result<(), &'static str>;
+/// Name of a mouse button, as used in profiles and recordings.
+pub(crate) fn mouse_button_name(button: MouseButton) -> &'static str {
+    match button {
+        MouseButton::Unknown => "Unknown",
+        MouseButton::Left => "Left",
+        MouseButton::Middle => "Middle",
+        MouseButton::Right => "Right",
+        MouseButton::X1 => "X1",
+        MouseButton::X2 => "X2",
+    }
+}
+
+pub(crate) fn mouse_button_from_name(name: &str) -> Option<MouseButton> {
+    match name {
+        "Unknown" => Some(MouseButton::Unknown),
+        "Left" => Some(MouseButton::Left),
+        "Middle" => Some(MouseButton::Middle),
+        "Right" => Some(MouseButton::Right),
+        "X1" => Some(MouseButton::X1),
+        "X2" => Some(MouseButton::X2),
+        _ => None,
+    }
+}
+
+type PlainCallback<State> = Fn(&mut State, PhysicalInput, PhysicalInputValue) -> PropagationResult;
+type ContextualCallback<State> =
+    Fn(&mut Context, &mut State, PhysicalInput, PhysicalInputValue) -> PropagationResult;
+
+enum LogicalInputCallback<State> {
+    Plain(Box<PlainCallback<State>>),
+    /// Only invoked when the event comes with a `ggez::Context`.
+    Contextual(Box<ContextualCallback<State>>),
+}
+
+pub type InputtyResult = Result<(), InputtyError>;
+pub type PropagationResult = Result<Propagation, InputtyError>;
+
+/// Whether other logical inputs get to see an event after a callback handled it.
+///
+/// Logical inputs completed by chords and sequences are invoked separately, after the bindings
+/// of the physical input itself: they aren't ordered by priority along with those, and `Stop`
+/// returned on either side doesn't affect the other.
+#[derive(PartialEq, Eq, Copy, Clone, Debug)]
+pub enum Propagation {
+    Continue,
+    /// Logical inputs later in dispatch order don't receive the event, nor its release; releases
+    /// themselves can't be stopped.
+    Stop,
+}
 
 /// A struct containing a mapping from physical input events to callbacks.
 pub struct InputHandler<LogicalInput, State>
 where
     LogicalInput: Hash + Eq + Clone + Debug,
 {
-    definitions: HashMap<LogicalInput, Box<LogicalInputCallback<State>>>,
-    bindings: HashMap<PhysicalInput, Vec<LogicalInput>>,
+    definitions: HashMap<LogicalInput, LogicalInputCallback<State>>,
+    /// Base binding context, always active.
+    bindings: BindingContext<LogicalInput>,
+    contexts: HashMap<String, BindingContext<LogicalInput>>,
+    context_stack: Vec<String>,
+    capture: Option<Capture<LogicalInput>>,
+    capture_result: Option<CaptureResult<LogicalInput>>,
+    /// Modifier keys as of the last key event.
+    keymod: Mod,
+    /// Bindings that were pressed via a physical input that wasn't released yet.
+    pressed: HashMap<PhysicalInput, Vec<(LogicalInput, BindingOptions)>>,
+    /// In order of first use, so that time-driven interactions fire in a stable order.
+    interaction_states: Vec<((PhysicalInput, LogicalInput), InteractionState)>,
+    chords: Vec<Chord<LogicalInput>>,
+    sequences: Vec<Sequence<LogicalInput>>,
+    /// Seconds since creation, as advanced by `update()` or set by `set_time()`.
+    time: f32,
+    logical_states: HashMap<LogicalInput, LogicalInputState>,
+    axis_thresholds: HashMap<Axis, AxisThreshold>,
+    /// Halves of controller axes that are currently past their press threshold.
+    axis_buttons: HashSet<PhysicalInput>,
+    error_policy: ErrorPolicy<LogicalInput>,
+    errors: Vec<CallbackError<LogicalInput>>,
+    priorities: HashMap<LogicalInput, i32>,
+    dispatch_mode: DispatchMode,
+    events: Vec<LogicalEvent<LogicalInput>>,
+    recorder: Option<Recorder>,
 }
 
-pub trait InputHandlerDefGen<LogicalInput, State, DefLogicalInput, DefState>
-where
-    LogicalInput: Hash + Eq + Clone + Debug,
-{
-    fn define_exp<F>(
+/// Defines several logical inputs at once, all driving a reusable state (a "widget", like
+/// `VirtualAxisState`) that `state_extractor` finds anywhere in the state.
+pub trait InputHandlerDefGen<LogicalInput, State, DefLogicalInput, DefState>
+where
+    LogicalInput: Hash + Eq + Clone + Debug,
+{
+    fn define_exp<F>(
+        &mut self,
+        logical: DefLogicalInput,
+        state_extractor: F,
+    ) -> &mut InputHandler<LogicalInput, State>
+    where
+        F: 'static + Fn(&mut State) -> &mut DefState;
+}
+
+impl<LogicalInput, State> InputHandler<LogicalInput, State>
+where
+    LogicalInput: Hash + Eq + Clone + Debug,
+{
+    pub fn new() -> Self {
+        InputHandler {
+            definitions: HashMap::new(),
+            bindings: BindingContext::new(false),
+            contexts: HashMap::new(),
+            context_stack: Vec::new(),
+            capture: None,
+            capture_result: None,
+            keymod: Mod::empty(),
+            pressed: HashMap::new(),
+            interaction_states: Vec::new(),
+            chords: Vec::new(),
+            sequences: Vec::new(),
+            time: 0.0,
+            logical_states: HashMap::new(),
+            axis_thresholds: HashMap::new(),
+            axis_buttons: HashSet::new(),
+            error_policy: ErrorPolicy::Log,
+            errors: Vec::new(),
+            priorities: HashMap::new(),
+            dispatch_mode: DispatchMode::Immediate,
+            events: Vec::new(),
+            recorder: None,
+        }
+    }
+
+    pub fn define<F>(&mut self, logical: LogicalInput, callback: F) -> &mut Self
+    where
+        F: 'static + Fn(&mut State, PhysicalInput, PhysicalInputValue) -> InputtyResult,
+    {
+        self.definitions.insert(
+            logical,
+            LogicalInputCallback::Plain(Box::new(move |state, physical, value| {
+                callback(state, physical, value).map(|()| Propagation::Continue)
+            })),
+        );
+        self
+    }
+
+    /// Same as `define()`, with a callback that can stop the event from propagating to logical
+    /// inputs later in dispatch order.
+    pub fn define_propagating<F>(&mut self, logical: LogicalInput, callback: F) -> &mut Self
+    where
+        F: 'static + Fn(&mut State, PhysicalInput, PhysicalInputValue) -> PropagationResult,
+    {
+        self.definitions
+            .insert(logical, LogicalInputCallback::Plain(Box::new(callback)));
+        self
+    }
+
+    /// Same as `define()`, with a callback that also receives `ggez::Context`; it's only invoked
+    /// by `*_with_context()` event methods, and skipped by the rest.
+    pub fn define_with_context<F>(&mut self, logical: LogicalInput, callback: F) -> &mut Self
+    where
+        F: 'static
+            + Fn(&mut Context, &mut State, PhysicalInput, PhysicalInputValue) -> InputtyResult,
+    {
+        self.definitions.insert(
+            logical,
+            LogicalInputCallback::Contextual(Box::new(move |ctx, state, physical, value| {
+                callback(ctx, state, physical, value).map(|()| Propagation::Continue)
+            })),
+        );
+        self
+    }
+
+    /// Sets dispatch priority of a logical input, used to order logical inputs bound to the
+    /// same physical input and with equal binding priority; higher goes first, default is 0.
+    pub fn set_priority(&mut self, logical: LogicalInput, priority: i32) -> &mut Self {
+        self.priorities.insert(logical, priority);
+        self
+    }
+
+    pub fn bind(&mut self, physical: PhysicalInput, logical: LogicalInput) -> &mut Self {
+        self.bindings.bind(physical, logical);
+        self
+    }
+
+    pub fn bind_with(
+        &mut self,
+        physical: PhysicalInput,
+        logical: LogicalInput,
+        options: BindingOptions,
+    ) -> &mut Self {
+        self.bindings.bind_with(physical, logical, options);
+        self
+    }
+
+    /// Removes the binding between given physical and logical inputs, if any.
+    pub fn unbind(&mut self, physical: PhysicalInput, logical: &LogicalInput) -> &mut Self {
+        self.bindings.unbind(physical, logical);
+        self
+    }
+
+    /// Removes every binding of given logical input.
+    pub fn unbind_all(&mut self, logical: &LogicalInput) -> &mut Self {
+        self.bindings.unbind_all(logical);
+        self
+    }
+
+    /// Replaces the `old` physical input of given logical input with the `new` one; does nothing
+    /// if `old` isn't bound to it.
+    pub fn rebind(
+        &mut self,
+        logical: LogicalInput,
+        old: PhysicalInput,
+        new: PhysicalInput,
+    ) -> &mut Self {
+        self.bindings.rebind(logical, old, new);
+        self
+    }
+
+    /// Every binding of the base context, as physical input, logical input and binding options;
+    /// grouped by physical input, in order of binding.
+    pub fn bindings<'a>(
+        &'a self,
+    ) -> impl Iterator<Item = (PhysicalInput, &'a LogicalInput, &'a BindingOptions)> + 'a {
+        self.bindings.bindings()
+    }
+
+    /// Removes every binding of the base context.
+    pub fn clear_bindings(&mut self) -> &mut Self {
+        self.bindings.clear_bindings();
+        self
+    }
+
+    /// Physical inputs bound to given logical input in the base context.
+    pub fn bindings_for<'a>(
+        &'a self,
+        logical: &LogicalInput,
+    ) -> impl Iterator<Item = PhysicalInput> + 'a {
+        self.bindings.bindings_for(logical)
+    }
+
+    /// Logical inputs bound to given physical input in the base context.
+    pub fn logicals_for<'a>(
+        &'a self,
+        physical: &PhysicalInput,
+    ) -> impl Iterator<Item = &'a LogicalInput> + 'a {
+        self.bindings.logicals_for(physical)
+    }
+
+    /// Binds a logical input to several physical inputs held together; it's pressed when the last
+    /// of them goes down, and released when any of them goes up. If `suppress` is set, members'
+    /// own bindings are released when the chord is pressed, and ignored while it's held.
+    ///
+    /// Members should be inputs that have both press and release events: keys, mouse buttons or
+    /// controller buttons. Chords are active regardless of binding contexts.
+    pub fn bind_chord(
+        &mut self,
+        members: &[PhysicalInput],
+        logical: LogicalInput,
+        suppress: bool,
+    ) -> &mut Self {
+        self.chords.push(Chord {
+            members: members.to_vec(),
+            logical,
+            suppress,
+            active: false,
+        });
+        self
+    }
+
+    /// Removes the chord binding between given physical inputs and logical input, if any.
+    pub fn unbind_chord(&mut self, members: &[PhysicalInput], logical: &LogicalInput) -> &mut Self {
+        self.chords
+            .retain(|chord| chord.members != members || chord.logical != *logical);
+        self
+    }
+
+    /// Binds a logical input to an ordered sequence of presses; it's pressed and immediately
+    /// released when the sequence is completed within the limits of given options.
+    ///
+    /// Logical steps are matched by logical inputs pressed via their bindings or chords.
+    pub fn bind_sequence(
+        &mut self,
+        steps: Vec<SequenceStep<LogicalInput>>,
+        logical: LogicalInput,
+        options: SequenceOptions,
+    ) -> &mut Self {
+        self.sequences.push(Sequence::new(steps, logical, options));
+        self
+    }
+
+    /// Removes every sequence binding of given logical input.
+    pub fn unbind_sequences(&mut self, logical: &LogicalInput) -> &mut Self {
+        self.sequences
+            .retain(|sequence| sequence.logical != *logical);
+        self
+    }
+
+    /// Sets what happens to errors returned by logical input callbacks.
+    pub fn set_error_policy(&mut self, error_policy: ErrorPolicy<LogicalInput>) -> &mut Self {
+        self.error_policy = error_policy;
+        self
+    }
+
+    /// Takes errors collected under `ErrorPolicy::Queue`, oldest first.
+    pub fn drain_errors<'a>(&'a mut self) -> Drain<'a, CallbackError<LogicalInput>> {
+        self.errors.drain(..)
+    }
+
+    /// Sets whether logical inputs receive values via callbacks, a queue of events, or both.
+    pub fn set_dispatch_mode(&mut self, dispatch_mode: DispatchMode) -> &mut Self {
+        self.dispatch_mode = dispatch_mode;
+        self
+    }
+
+    /// Takes events queued under `DispatchMode::Queued` or `Both`, oldest first.
+    pub fn drain_events<'a>(&'a mut self) -> Drain<'a, LogicalEvent<LogicalInput>> {
+        self.events.drain(..)
+    }
+
+    /// Starts noting every dispatched physical input event; see `recording` module. Discards
+    /// the ongoing recording, if any.
+    pub fn start_recording(&mut self) -> &mut Self {
+        self.recorder = Some(Recorder::new(self.time));
+        self
+    }
+
+    /// Stops recording, and returns what was recorded; `None` if there wasn't a recording.
+    pub fn stop_recording(&mut self) -> Option<Recording> {
+        self.recorder.take().map(Recorder::finish)
+    }
+
+    pub fn is_recording(&self) -> bool {
+        self.recorder.is_some()
+    }
+
+    /// Dispatches a recorded event as it was, with modifier keys held at the time; see
+    /// `replay::Replay` for replaying whole recordings.
+    pub fn replay_event(
+        &mut self,
+        state: &mut State,
+        event: &RecordedEvent,
+    ) -> DispatchResult<LogicalInput> {
+        self.keymod = event.keymod;
+        self.resolve_and_invoke(state, event.physical, event.value)
+    }
+
+    /// Logical inputs without a definition that given physical inputs can reach, via bindings
+    /// in any context, chords, or sequences.
+    pub fn undefined_logicals(&self, physicals: &[PhysicalInput]) -> Vec<LogicalInput> {
+        let mut reachable = Vec::new();
+        for physical in physicals {
+            let contexts = Some(&self.bindings)
+                .into_iter()
+                .chain(self.contexts.values());
+            for context in contexts {
+                reachable.extend(context.logicals_for(physical).cloned());
+            }
+            for chord in &self.chords {
+                if chord.members.contains(physical) {
+                    reachable.push(chord.logical.clone());
+                }
+            }
+        }
+        for sequence in &self.sequences {
+            let reached = sequence.steps.iter().any(|step| match *step {
+                SequenceStep::Physical(ref physical) => physicals.contains(physical),
+                SequenceStep::Logical(ref logical) => reachable.contains(logical),
+            });
+            if reached {
+                reachable.push(sequence.logical.clone());
+            }
+        }
+        let mut undefined = Vec::new();
+        for logical in reachable {
+            if !self.definitions.contains_key(&logical) && !undefined.contains(&logical) {
+                undefined.push(logical);
+            }
+        }
+        undefined
+    }
+
+    /// Sets thresholds at which halves of given axis, on any controller, are pressed and
+    /// released as `PhysicalInput::CAxisButton`.
+    pub fn set_axis_threshold(&mut self, axis: Axis, threshold: AxisThreshold) -> &mut Self {
+        self.axis_thresholds.insert(axis, threshold);
+        self
+    }
+
+    /// Thresholds set with `set_axis_threshold()`, in no particular order.
+    pub fn axis_thresholds<'a>(&'a self) -> impl Iterator<Item = (Axis, &'a AxisThreshold)> + 'a {
+        self.axis_thresholds
+            .iter()
+            .map(|(&axis, threshold)| (axis, threshold))
+    }
+
+    /// Advances time of the handler by given amount of seconds, and dispatches presses of
+    /// time-driven interactions.
+    pub fn update(&mut self, state: &mut State, delta_time: f32) -> DispatchResult<LogicalInput> {
+        self.advance(&mut None, state, delta_time)
+    }
+
+    pub fn update_with_context(
+        &mut self,
+        ctx: &mut Context,
+        state: &mut State,
+        delta_time: f32,
+    ) -> DispatchResult<LogicalInput> {
+        self.advance(&mut Some(ctx), state, delta_time)
+    }
+
+    fn advance(
+        &mut self,
+        ctx: &mut Option<&mut Context>,
+        state: &mut State,
+        delta_time: f32,
+    ) -> DispatchResult<LogicalInput> {
+        self.time += delta_time;
+        if let Some(ref mut recorder) = self.recorder {
+            recorder.end_frame(self.time);
+        }
+        let time = self.time;
+        let pressed = &self.pressed;
+        let priorities = &self.priorities;
+        let mut fired = Vec::new();
+        for &mut ((physical, ref logical), ref mut interaction_state) in
+            &mut self.interaction_states
+        {
+            let options = pressed.get(&physical).and_then(|bindings| {
+                bindings
+                    .iter()
+                    .find(|&&(ref bound, _)| bound == logical)
+                    .map(|&(_, ref options)| options)
+            });
+            if let Some(options) = options {
+                if let Some(value) = options.interaction.tick(interaction_state, time) {
+                    let priority = priorities.get(logical).cloned().unwrap_or(0);
+                    let order = (Reverse(options.priority), Reverse(priority));
+                    fired.push((order, physical, logical.clone(), value));
+                }
+            }
+        }
+        // Same order as `resolve()`; ties keep the order of first use.
+        fired.sort_by_key(|&(order, ..)| order);
+        for (_, physical, logical, value) in fired {
+            self.invoke(ctx, state, &[(logical, value)], physical, true)?;
+        }
+        Ok(())
+    }
+
+    /// Sets time of the handler, in seconds; allows feeding it exact timestamps. Time-driven
+    /// interactions are only advanced by `update()`, which can be given zero delta time.
+    pub fn set_time(&mut self, time: f32) {
+        self.time = time;
+    }
+
+    pub fn time(&self) -> f32 {
+        self.time
+    }
+
+    /// Pollable state of given logical input; it doesn't need to be defined to be tracked.
+    pub fn logical_state(&self, logical: &LogicalInput) -> LogicalInputState {
+        self.logical_states
+            .get(logical)
+            .cloned()
+            .unwrap_or_default()
+    }
+
+    pub fn is_down(&self, logical: &LogicalInput) -> bool {
+        self.logical_state(logical).is_down()
+    }
+
+    pub fn just_pressed(&self, logical: &LogicalInput) -> bool {
+        self.logical_state(logical).just_pressed()
+    }
+
+    pub fn just_released(&self, logical: &LogicalInput) -> bool {
+        self.logical_state(logical).just_released()
+    }
+
+    pub fn value_f32(&self, logical: &LogicalInput) -> f32 {
+        self.logical_state(logical).value_f32()
+    }
+
+    /// Clears `just_pressed()` and `just_released()` edges; call once per frame, after reading
+    /// them.
+    pub fn end_frame(&mut self) {
+        for logical_state in self.logical_states.values_mut() {
+            logical_state.end_frame();
+        }
+    }
+
+    /// Creates (or replaces) a named binding context, returning it for binding.
+    pub fn define_context(
+        &mut self,
+        name: &str,
+        fallthrough: bool,
+    ) -> &mut BindingContext<LogicalInput> {
+        self.contexts
+            .insert(name.to_owned(), BindingContext::new(fallthrough));
+        self.contexts.get_mut(name).unwrap()
+    }
+
+    /// Names of defined binding contexts, in no particular order.
+    pub fn context_names<'a>(&'a self) -> impl Iterator<Item = &'a str> + 'a {
+        self.contexts.keys().map(|name| name.as_str())
+    }
+
+    pub fn context(&self, name: &str) -> Option<&BindingContext<LogicalInput>> {
+        self.contexts.get(name)
+    }
+
+    pub fn context_mut(&mut self, name: &str) -> Option<&mut BindingContext<LogicalInput>> {
+        self.contexts.get_mut(name)
+    }
+
+    /// Makes a defined binding context the topmost active one.
+    pub fn push_context(&mut self, name: &str) -> InputtyResult {
+        if !self.contexts.contains_key(name) {
+            return Err(InputtyError::new(format!(
+                "Undefined binding context `{}`",
+                name
+            )));
+        }
+        self.context_stack.push(name.to_owned());
+        Ok(())
+    }
+
+    /// Deactivates the topmost active binding context, returning its name.
+    pub fn pop_context(&mut self) -> Option<String> {
+        self.context_stack.pop()
+    }
+
+    /// Names of active binding contexts, from bottom to top; the base context isn't included.
+    pub fn context_stack(&self) -> &[String] {
+        &self.context_stack
+    }
+
+    /// Finds bindings of given physical input, walking the context stack from the top, in
+    /// dispatch order: by binding priority, then by logical input priority, both descending,
+    /// then in order of binding.
+    fn resolve(&self, physical: &PhysicalInput) -> Vec<(LogicalInput, BindingOptions)> {
+        let mut logicals = self.resolve_unordered(physical);
+        let priorities = &self.priorities;
+        logicals.sort_by_key(|&(ref logical, ref options)| {
+            let priority = priorities.get(logical).cloned().unwrap_or(0);
+            (Reverse(options.priority), Reverse(priority))
+        });
+        logicals
+    }
+
+    fn resolve_unordered(&self, physical: &PhysicalInput) -> Vec<(LogicalInput, BindingOptions)> {
+        for name in self.context_stack.iter().rev() {
+            if let Some(context) = self.contexts.get(name) {
+                let logicals = context.resolve(physical, self.keymod);
+                if !logicals.is_empty() || !context.fallthrough() {
+                    return logicals;
+                }
+            }
+        }
+        self.bindings.resolve(physical, self.keymod)
+    }
+
+    /// Makes the next qualifying physical input bind to given logical input instead of being
+    /// dispatched; see `CaptureOptions::default()` for what qualifies.
+    pub fn begin_capture(&mut self, logical: LogicalInput) -> &mut Self {
+        self.begin_capture_with(logical, CaptureOptions::default())
+    }
+
+    /// Same as `begin_capture()`, with explicit options.
+    pub fn begin_capture_with(
+        &mut self,
+        logical: LogicalInput,
+        options: CaptureOptions,
+    ) -> &mut Self {
+        self.capture = Some(Capture { logical, options });
+        self.capture_result = None;
+        self
+    }
+
+    /// Stops an ongoing capture, if any, as if it was cancelled.
+    pub fn cancel_capture(&mut self) -> &mut Self {
+        if let Some(capture) = self.capture.take() {
+            self.capture_result = Some(CaptureResult::Cancelled(capture.logical));
+        }
+        self
+    }
+
+    pub fn is_capturing(&self) -> bool {
+        self.capture.is_some()
+    }
+
+    /// Outcome of the last finished capture; `None` if there isn't one, or it was already taken.
+    pub fn take_capture_result(&mut self) -> Option<CaptureResult<LogicalInput>> {
+        self.capture_result.take()
+    }
+
+    pub fn resolve_and_invoke(
+        &mut self,
+        state: &mut State,
+        physical: PhysicalInput,
+        value: PhysicalInputValue,
+    ) -> DispatchResult<LogicalInput> {
+        self.dispatch(&mut None, state, physical, value)
+    }
+
+    pub fn resolve_and_invoke_with_context(
+        &mut self,
+        ctx: &mut Context,
+        state: &mut State,
+        physical: PhysicalInput,
+        value: PhysicalInputValue,
+    ) -> DispatchResult<LogicalInput> {
+        self.dispatch(&mut Some(ctx), state, physical, value)
+    }
+
+    fn dispatch(
+        &mut self,
+        ctx: &mut Option<&mut Context>,
+        state: &mut State,
+        physical: PhysicalInput,
+        value: PhysicalInputValue,
+    ) -> DispatchResult<LogicalInput> {
+        if let Some(ref mut recorder) = self.recorder {
+            recorder.record(self.time, physical, value, self.keymod);
+        }
+        // Releases of inputs pressed before the capture began aren't captured, but still go
+        // where their presses went.
+        let releases_pressed = match (value, self.pressed.get(&physical)) {
+            (PhysicalInputValue::Button(false), Some(bindings)) => !bindings.is_empty(),
+            _ => false,
+        };
+        if let Some(result) = self
+            .capture
+            .as_ref()
+            .filter(|_| !releases_pressed)
+            .map(|capture| capture.process(physical, value))
+        {
+            if let Some(result) = result {
+                debug!("Capture finished: {:?}", result);
+                if let CaptureResult::Bound(ref logical, physical) = result {
+                    if !self.bindings_for(logical).any(|bound| bound == physical) {
+                        self.bind(physical, logical.clone());
+                    }
+                }
+                self.capture = None;
+                self.capture_result = Some(result);
+            }
+            // The release of whatever was pressed during capture is swallowed as well.
+            if let PhysicalInputValue::Button(true) = value {
+                self.pressed.insert(physical, Vec::new());
+            }
+            return Ok(());
+        }
+        let mut chorded = Vec::new();
+        let mut released_members = Vec::new();
+        for chord in &mut self.chords {
+            let pressed = &self.pressed;
+            match chord.update(physical, value, |member| pressed.contains_key(member)) {
+                Some(true) => {
+                    chorded.push((chord.logical.clone(), value));
+                    if chord.suppress {
+                        released_members.extend(
+                            chord
+                                .members
+                                .iter()
+                                .filter(|member| **member != physical)
+                                .cloned(),
+                        );
+                    }
+                }
+                Some(false) => chorded.push((chord.logical.clone(), value)),
+                None => (),
+            }
+        }
+        for member in released_members {
+            if let Some(bindings) = self.pressed.insert(member, Vec::new()) {
+                let mut released = Vec::new();
+                for (logical, options) in bindings {
+                    let key = (member, logical);
+                    let index = self
+                        .interaction_states
+                        .iter()
+                        .position(|entry| entry.0 == key);
+                    let value = match index.map(|index| self.interaction_states.remove(index)) {
+                        Some((_, mut interaction_state)) => {
+                            options.interaction.cancel(&mut interaction_state)
+                        }
+                        None => match options.interaction {
+                            Interaction::Press => Some(PhysicalInputValue::Button(false)),
+                            _ => None,
+                        },
+                    };
+                    if let Some(value) = value {
+                        released.push((key.1, value));
+                    }
+                }
+                self.invoke(ctx, state, &released, member, false)?;
+            }
+        }
+        let suppressed = self.chords.iter().any(|chord| chord.suppresses(&physical));
+
+        // Releases go to whatever the press went to, regardless of modifiers or contexts, or
+        // whether the press itself was filtered out by repeat policy.
+        let bindings = match value {
+            PhysicalInputValue::Button(false) => match self.pressed.remove(&physical) {
+                Some(bindings) => bindings,
+                None => self.resolve(&physical),
+            },
+            _ if suppressed => {
+                if let PhysicalInputValue::Button(true) = value {
+                    self.pressed.insert(physical, Vec::new());
+                }
+                Vec::new()
+            }
+            _ => {
+                let bindings = self.resolve(&physical);
+                if let PhysicalInputValue::Button(true) = value {
+                    self.pressed.insert(physical, bindings.clone());
+                }
+                bindings
+            }
+        };
+        let mut dispatched = Vec::new();
+        for (logical, options) in bindings {
+            if let PhysicalInput::Key(..) = physical {
+                if !options.repeat.accepts(value) {
+                    continue;
+                }
+            }
+            let value = match (options.normalization, value) {
+                (Some(normalization), PhysicalInputValue::Axis(raw_axis)) => {
+                    PhysicalInputValue::Axis(normalization.apply(raw_axis))
+                }
+                _ => value,
+            };
+            let values = match (options.wheel, value) {
+                (WheelMode::Buttons, PhysicalInputValue::Wheel(delta)) => (0..delta.abs())
+                    .flat_map(|_| {
+                        vec![
+                            PhysicalInputValue::Button(true),
+                            PhysicalInputValue::Button(false),
+                        ]
+                    })
+                    .collect(),
+                _ => vec![value],
+            };
+            if let Interaction::Press = options.interaction {
+                dispatched.extend(values.into_iter().map(|value| (logical.clone(), value)));
+                continue;
+            }
+            let key = (physical, logical);
+            for value in values {
+                let values = {
+                    let index = match self
+                        .interaction_states
+                        .iter()
+                        .position(|entry| entry.0 == key)
+                    {
+                        Some(index) => index,
+                        None => {
+                            self.interaction_states
+                                .push((key.clone(), InteractionState::default()));
+                            self.interaction_states.len() - 1
+                        }
+                    };
+                    let interaction_state = &mut self.interaction_states[index].1;
+                    options
+                        .interaction
+                        .process(interaction_state, value, self.time)
+                };
+                dispatched.extend(values.into_iter().map(|value| (key.1.clone(), value)));
+            }
+        }
+        // Releases reach everything the press did, so that nothing is left held.
+        let stoppable = value != PhysicalInputValue::Button(false);
+        let (invoked, result) = self.invoke_counted(ctx, state, &dispatched, physical, stoppable);
+        if invoked < dispatched.len() && value == PhysicalInputValue::Button(true) {
+            debug!("Propagation of {:?} stopped", physical);
+            let skipped = dispatched
+                .split_off(invoked)
+                .into_iter()
+                .map(|(logical, _)| logical)
+                .filter(|logical| {
+                    !dispatched
+                        .iter()
+                        .any(|&(ref invoked, _)| invoked == logical)
+                })
+                .collect::<Vec<_>>();
+            if let Some(bindings) = self.pressed.get_mut(&physical) {
+                bindings.retain(|&(ref logical, _)| !skipped.contains(logical));
+            }
+        }
+        result?;
+        self.invoke(ctx, state, &chorded, physical, stoppable)?;
+
+        if let PhysicalInputValue::Button(true) | PhysicalInputValue::Wheel(..) = value {
+            let pressed = dispatched
+                .into_iter()
+                .chain(chorded)
+                .filter(|&(_, value)| value == PhysicalInputValue::Button(true))
+                .map(|(logical, _)| logical)
+                .collect::<Vec<_>>();
+            let time = self.time;
+            let completed = self
+                .sequences
+                .iter_mut()
+                .filter_map(|sequence| {
+                    if sequence.press(physical, &pressed, time) {
+                        Some(sequence.logical.clone())
+                    } else {
+                        None
+                    }
+                })
+                .collect::<Vec<_>>();
+            if !completed.is_empty() {
+                debug!("Sequences completed: {:?}", completed);
+                let mut tapped = Vec::new();
+                for logical in completed {
+                    tapped.push((logical.clone(), PhysicalInputValue::Button(true)));
+                    tapped.push((logical, PhysicalInputValue::Button(false)));
+                }
+                self.invoke(ctx, state, &tapped, physical, true)?;
+            }
+        }
+        Ok(())
+    }
+
+    /// Invokes callbacks in order, until one stops propagation, if `stoppable`; returns how
+    /// many were invoked.
+    fn invoke(
+        &mut self,
+        ctx: &mut Option<&mut Context>,
+        state: &mut State,
+        dispatched: &[(LogicalInput, PhysicalInputValue)],
+        physical: PhysicalInput,
+        stoppable: bool,
+    ) -> Result<usize, CallbackError<LogicalInput>> {
+        match self.invoke_counted(ctx, state, dispatched, physical, stoppable) {
+            (invoked, Ok(())) => Ok(invoked),
+            (_, Err(error)) => Err(error),
+        }
+    }
+
+    /// Same as `invoke()`, but also tells how many were invoked when aborted by an error.
+    fn invoke_counted(
+        &mut self,
+        ctx: &mut Option<&mut Context>,
+        state: &mut State,
+        dispatched: &[(LogicalInput, PhysicalInputValue)],
+        physical: PhysicalInput,
+        stoppable: bool,
+    ) -> (usize, DispatchResult<LogicalInput>) {
+        for (index, &(ref logical, value)) in dispatched.iter().enumerate() {
+            self.logical_states
+                .entry(logical.clone())
+                .or_insert_with(LogicalInputState::default)
+                .dispatch(value);
+            if self.dispatch_mode != DispatchMode::Immediate {
+                self.events.push(LogicalEvent {
+                    logical: logical.clone(),
+                    physical,
+                    value,
+                    timestamp: self.time,
+                });
+                if self.dispatch_mode == DispatchMode::Queued {
+                    continue;
+                }
+            }
+            let result = match self.definitions.get(logical) {
+                Some(&LogicalInputCallback::Plain(ref callback)) => {
+                    callback(state, physical, value)
+                }
+                Some(&LogicalInputCallback::Contextual(ref callback)) => match *ctx {
+                    Some(ref mut ctx) => callback(ctx, state, physical, value),
+                    None => {
+                        debug!(
+                            "Skipped {:?}, as there's no context to invoke it with",
+                            logical
+                        );
+                        continue;
+                    }
+                },
+                None => continue,
+            };
+            match result {
+                Ok(Propagation::Stop) if stoppable => return (index + 1, Ok(())),
+                Ok(_) => (),
+                Err(error) => {
+                    let error = CallbackError {
+                        logical: logical.clone(),
+                        physical,
+                        value,
+                        error,
+                    };
+                    match self.error_policy {
+                        ErrorPolicy::Log => error!("{}", error),
+                        ErrorPolicy::Queue => self.errors.push(error),
+                        ErrorPolicy::Hook(ref mut hook) => hook(error),
+                        ErrorPolicy::Abort => return (index + 1, Err(error)),
+                    }
+                }
+            }
+        }
+        (dispatched.len(), Ok(()))
+    }
+
+    pub fn mouse_button_down_event(
+        &mut self,
+        state: &mut State,
+        button: MouseButton,
+        x: i32,
+        y: i32,
+    ) -> DispatchResult<LogicalInput> {
+        self.on_mouse_button_down(&mut None, state, button, x, y)
+    }
+
+    pub fn mouse_button_down_event_with_context(
+        &mut self,
+        ctx: &mut Context,
+        state: &mut State,
+        button: MouseButton,
+        x: i32,
+        y: i32,
+    ) -> DispatchResult<LogicalInput> {
+        self.on_mouse_button_down(&mut Some(ctx), state, button, x, y)
+    }
+
+    pub fn mouse_button_up_event(
+        &mut self,
+        state: &mut State,
+        button: MouseButton,
+        x: i32,
+        y: i32,
+    ) -> DispatchResult<LogicalInput> {
+        self.on_mouse_button_up(&mut None, state, button, x, y)
+    }
+
+    pub fn mouse_button_up_event_with_context(
+        &mut self,
+        ctx: &mut Context,
+        state: &mut State,
+        button: MouseButton,
+        x: i32,
+        y: i32,
+    ) -> DispatchResult<LogicalInput> {
+        self.on_mouse_button_up(&mut Some(ctx), state, button, x, y)
+    }
+
+    pub fn mouse_motion_event(
+        &mut self,
+        state: &mut State,
+        mouse_state: MouseState,
+        x: i32,
+        y: i32,
+        xrel: i32,
+        yrel: i32,
+    ) -> DispatchResult<LogicalInput> {
+        self.on_mouse_motion(&mut None, state, mouse_state, x, y, xrel, yrel)
+    }
+
+    pub fn mouse_motion_event_with_context(
+        &mut self,
+        ctx: &mut Context,
+        state: &mut State,
+        mouse_state: MouseState,
+        x: i32,
+        y: i32,
+        xrel: i32,
+        yrel: i32,
+    ) -> DispatchResult<LogicalInput> {
+        self.on_mouse_motion(&mut Some(ctx), state, mouse_state, x, y, xrel, yrel)
+    }
+
+    pub fn mouse_wheel_event(
+        &mut self,
+        state: &mut State,
+        x: i32,
+        y: i32,
+    ) -> DispatchResult<LogicalInput> {
+        self.on_mouse_wheel(&mut None, state, x, y)
+    }
+
+    pub fn mouse_wheel_event_with_context(
+        &mut self,
+        ctx: &mut Context,
+        state: &mut State,
+        x: i32,
+        y: i32,
+    ) -> DispatchResult<LogicalInput> {
+        self.on_mouse_wheel(&mut Some(ctx), state, x, y)
+    }
+
+    pub fn key_down_event(
+        &mut self,
+        state: &mut State,
+        keycode: Keycode,
+        keymod: Mod,
+        repeat: bool,
+    ) -> DispatchResult<LogicalInput> {
+        self.on_key_down(&mut None, state, keycode, keymod, repeat)
+    }
+
+    pub fn key_down_event_with_context(
+        &mut self,
+        ctx: &mut Context,
+        state: &mut State,
+        keycode: Keycode,
+        keymod: Mod,
+        repeat: bool,
+    ) -> DispatchResult<LogicalInput> {
+        self.on_key_down(&mut Some(ctx), state, keycode, keymod, repeat)
+    }
+
+    pub fn key_up_event(
+        &mut self,
+        state: &mut State,
+        keycode: Keycode,
+        keymod: Mod,
+        repeat: bool,
+    ) -> DispatchResult<LogicalInput> {
+        self.on_key_up(&mut None, state, keycode, keymod, repeat)
+    }
+
+    pub fn key_up_event_with_context(
+        &mut self,
+        ctx: &mut Context,
+        state: &mut State,
+        keycode: Keycode,
+        keymod: Mod,
+        repeat: bool,
+    ) -> DispatchResult<LogicalInput> {
+        self.on_key_up(&mut Some(ctx), state, keycode, keymod, repeat)
+    }
+
+    pub fn controller_button_down_event(
         &mut self,
-        logical: DefLogicalInput,
-        state_extractor: F,
-    ) -> &mut InputHandler<LogicalInput, State>
-    where
-        F: 'static + Fn(&mut State) -> &mut DefState;
-}
+        state: &mut State,
+        button: Button,
+        instance_id: i32,
+    ) -> DispatchResult<LogicalInput> {
+        self.on_controller_button_down(&mut None, state, button, instance_id)
+    }
 
-impl<LogicalInput, State> InputHandler<LogicalInput, State>
-where
-    LogicalInput: Hash + Eq + Clone + Debug,
-{
-    pub fn new() -> Self {
-        InputHandler {
-            definitions: HashMap::new(),
-            bindings: HashMap::new(),
-        }
+    pub fn controller_button_down_event_with_context(
+        &mut self,
+        ctx: &mut Context,
+        state: &mut State,
+        button: Button,
+        instance_id: i32,
+    ) -> DispatchResult<LogicalInput> {
+        self.on_controller_button_down(&mut Some(ctx), state, button, instance_id)
     }
 
-    pub fn define<F>(&mut self, logical: LogicalInput, callback: F) -> &mut Self
-    where
-        F: 'static + Fn(&mut State, PhysicalInput, PhysicalInputValue) -> InputtyResult,
-    {
-        self.definitions.insert(logical, Box::new(callback));
-        self
+    pub fn controller_button_up_event(
+        &mut self,
+        state: &mut State,
+        button: Button,
+        instance_id: i32,
+    ) -> DispatchResult<LogicalInput> {
+        self.on_controller_button_up(&mut None, state, button, instance_id)
     }
 
-    pub fn bind(&mut self, physical: PhysicalInput, logical: LogicalInput) -> &mut Self {
-        self.bindings
-            .entry(physical)
-            .or_insert_with(Vec::new)
-            .push(logical);
-        self
+    pub fn controller_button_up_event_with_context(
+        &mut self,
+        ctx: &mut Context,
+        state: &mut State,
+        button: Button,
+        instance_id: i32,
+    ) -> DispatchResult<LogicalInput> {
+        self.on_controller_button_up(&mut Some(ctx), state, button, instance_id)
     }
 
-    pub fn resolve_and_invoke(
+    pub fn controller_axis_event(
         &mut self,
         state: &mut State,
-        physical: PhysicalInput,
-        value: PhysicalInputValue,
-    ) {
-        if let Some(bindings) = self.bindings.get(&physical) {
-            for logical in bindings {
-                if let Some(callback) = self.definitions.get(logical) {
-                    if let Err(e) = callback(state, physical, value) {
-                        error!(
-                            "Logical input callback {:?} ( {:?}, {:?} ) returned an error: {}",
-                            logical, physical, value, e
-                        );
-                    }
-                }
-            }
-        }
+        axis: Axis,
+        value: i16,
+        instance_id: i32,
+    ) -> DispatchResult<LogicalInput> {
+        self.on_controller_axis(&mut None, state, axis, value, instance_id)
     }
 
-    pub fn mouse_button_down_event(
+    pub fn controller_axis_event_with_context(
+        &mut self,
+        ctx: &mut Context,
+        state: &mut State,
+        axis: Axis,
+        value: i16,
+        instance_id: i32,
+    ) -> DispatchResult<LogicalInput> {
+        self.on_controller_axis(&mut Some(ctx), state, axis, value, instance_id)
+    }
+
+    fn on_mouse_button_down(
         &mut self,
+        ctx: &mut Option<&mut Context>,
         state: &mut State,
         button: MouseButton,
         _x: i32,
         _y: i32,
-    ) {
+    ) -> DispatchResult<LogicalInput> {
         trace!(
             "raw mouse button down: {:?} | x: {} | y: {} | instance: {}",
             button,
@@ -120,20 +1170,23 @@ where
             _y,
             0
         );
-        self.resolve_and_invoke(
+        self.dispatch(
+            ctx,
             state,
             PhysicalInput::MButton(button),
             PhysicalInputValue::Button(true),
-        );
+        )?;
+        Ok(())
     }
 
-    pub fn mouse_button_up_event(
+    fn on_mouse_button_up(
         &mut self,
+        ctx: &mut Option<&mut Context>,
         state: &mut State,
         button: MouseButton,
         _x: i32,
         _y: i32,
-    ) {
+    ) -> DispatchResult<LogicalInput> {
         trace!(
             "raw mouse button up: {:?} | x: {} | y: {} | instance: {}",
             button,
@@ -141,22 +1194,25 @@ where
             _y,
             0
         );
-        self.resolve_and_invoke(
+        self.dispatch(
+            ctx,
             state,
             PhysicalInput::MButton(button),
             PhysicalInputValue::Button(false),
-        );
+        )?;
+        Ok(())
     }
 
-    pub fn mouse_motion_event(
+    fn on_mouse_motion(
         &mut self,
+        ctx: &mut Option<&mut Context>,
         state: &mut State,
         _mouse_state: MouseState,
         x: i32,
         y: i32,
         xrel: i32,
         yrel: i32,
-    ) {
+    ) -> DispatchResult<LogicalInput> {
         trace!(
             "raw mouse motion: x: {} | y: {} | xrel: {} | yrel: {} | instance: {}",
             x,
@@ -165,152 +1221,1200 @@ where
             yrel,
             0,
         );
-        self.resolve_and_invoke(
+        self.dispatch(
+            ctx,
             state,
             PhysicalInput::MMotion,
             PhysicalInputValue::XY(x, y, xrel, yrel),
-        );
+        )?;
+        Ok(())
     }
 
-    pub fn mouse_wheel_event(&mut self, state: &mut State, x: i32, y: i32) {
+    fn on_mouse_wheel(
+        &mut self,
+        ctx: &mut Option<&mut Context>,
+        state: &mut State,
+        x: i32,
+        y: i32,
+    ) -> DispatchResult<LogicalInput> {
         trace!("raw mouse wheel: x: {} | y: {} | instance: {}", x, y, 0);
-        let (mut x, mut y) = (x, y);
-        if x > 0 {
-            while x > 0 {
-                self.resolve_and_invoke(
-                    state,
-                    PhysicalInput::MWheelX(true),
-                    PhysicalInputValue::Button(true),
-                );
-                x -= 1;
-            }
-        } else if x < 0 {
-            while x < 0 {
-                self.resolve_and_invoke(
-                    state,
-                    PhysicalInput::MWheelX(false),
-                    PhysicalInputValue::Button(true),
-                );
-                x += 1;
-            }
+        if x != 0 {
+            self.dispatch(
+                ctx,
+                state,
+                PhysicalInput::MWheelX(x > 0),
+                PhysicalInputValue::Wheel(x),
+            )?;
         }
-        if y > 0 {
-            while y > 0 {
-                self.resolve_and_invoke(
-                    state,
-                    PhysicalInput::MWheelY(true),
-                    PhysicalInputValue::Button(true),
-                );
-
-                y -= 1;
-            }
-        } else if y < 0 {
-            while y < 0 {
-                self.resolve_and_invoke(
-                    state,
-                    PhysicalInput::MWheelY(false),
-                    PhysicalInputValue::Button(true),
-                );
-                y += 1;
-            }
+        if y != 0 {
+            self.dispatch(
+                ctx,
+                state,
+                PhysicalInput::MWheelY(y > 0),
+                PhysicalInputValue::Wheel(y),
+            )?;
         }
+        Ok(())
     }
 
-    pub fn key_down_event(
+    fn on_key_down(
         &mut self,
+        ctx: &mut Option<&mut Context>,
         state: &mut State,
         keycode: Keycode,
-        _keymod: Mod,
+        keymod: Mod,
         repeat: bool,
-    ) {
+    ) -> DispatchResult<LogicalInput> {
         trace!(
             "raw key down: {} | modifiers: {:?} | repeat: {} | instance: {}",
             keycode,
-            _keymod,
+            keymod,
             repeat,
             0,
         );
-        self.resolve_and_invoke(
+        self.keymod = keymod;
+        self.dispatch(
+            ctx,
             state,
-            PhysicalInput::Key(keycode, repeat),
-            PhysicalInputValue::Button(true),
-        );
+            PhysicalInput::Key(keycode),
+            if repeat {
+                PhysicalInputValue::Repeat
+            } else {
+                PhysicalInputValue::Button(true)
+            },
+        )?;
+        Ok(())
     }
 
-    pub fn key_up_event(
+    fn on_key_up(
         &mut self,
+        ctx: &mut Option<&mut Context>,
         state: &mut State,
         keycode: Keycode,
-        _keymod: Mod,
+        keymod: Mod,
         repeat: bool,
-    ) {
+    ) -> DispatchResult<LogicalInput> {
         trace!(
             "raw key up: {} | modifiers: {:?} | repeat: {} | instance: {}",
             keycode,
-            _keymod,
+            keymod,
             repeat,
             0,
         );
-        self.resolve_and_invoke(
+        self.keymod = keymod;
+        self.dispatch(
+            ctx,
             state,
-            PhysicalInput::Key(keycode, repeat),
+            PhysicalInput::Key(keycode),
             PhysicalInputValue::Button(false),
-        );
+        )?;
+        Ok(())
     }
 
-    pub fn controller_button_down_event(
+    fn on_controller_button_down(
         &mut self,
+        ctx: &mut Option<&mut Context>,
         state: &mut State,
         button: Button,
         instance_id: i32,
-    ) {
+    ) -> DispatchResult<LogicalInput> {
         trace!("raw button down: {:?} | instance: {}", button, instance_id,);
-        self.resolve_and_invoke(
+        self.dispatch(
+            ctx,
             state,
             PhysicalInput::CButton(instance_id, button),
             PhysicalInputValue::Button(true),
-        );
+        )?;
+        Ok(())
     }
 
-    pub fn controller_button_up_event(
+    fn on_controller_button_up(
         &mut self,
+        ctx: &mut Option<&mut Context>,
         state: &mut State,
         button: Button,
         instance_id: i32,
-    ) {
+    ) -> DispatchResult<LogicalInput> {
         trace!("raw button up: {:?} | instance: {}", button, instance_id,);
-        self.resolve_and_invoke(
+        self.dispatch(
+            ctx,
             state,
             PhysicalInput::CButton(instance_id, button),
             PhysicalInputValue::Button(false),
-        );
+        )?;
+        Ok(())
     }
 
-    pub fn controller_axis_event(
+    fn on_controller_axis(
         &mut self,
+        ctx: &mut Option<&mut Context>,
         state: &mut State,
         axis: Axis,
         value: i16,
         instance_id: i32,
-    ) {
+    ) -> DispatchResult<LogicalInput> {
         trace!(
             "raw axis event: {:?} | {} | instance: {}",
             axis,
             value,
             instance_id
         );
-        self.resolve_and_invoke(
+        let captured = self.capture.is_some();
+        self.dispatch(
+            ctx,
             state,
             PhysicalInput::CAxis(instance_id, axis),
             PhysicalInputValue::Axis(value),
-        );
+        )?;
+        let threshold = self.axis_thresholds.get(&axis).cloned().unwrap_or_default();
+        let deflection = threshold.normalization.normalize(value);
+        let halves = [(true, deflection), (false, -deflection)];
+        // Releases go first, so that flicking the axis across doesn't overlap the halves.
+        for &(positive, deflection) in &halves {
+            let half = PhysicalInput::CAxisButton(instance_id, axis, positive);
+            if deflection <= threshold.release && self.axis_buttons.remove(&half) {
+                self.dispatch(ctx, state, half, PhysicalInputValue::Button(false))?;
+            }
+        }
+        for &(positive, deflection) in &halves {
+            let half = PhysicalInput::CAxisButton(instance_id, axis, positive);
+            if deflection >= threshold.press && self.axis_buttons.insert(half) {
+                if captured {
+                    // Swallowed along with the axis event, release included.
+                    self.pressed.insert(half, Vec::new());
+                } else {
+                    self.dispatch(ctx, state, half, PhysicalInputValue::Button(true))?;
+                }
+            }
+        }
+        Ok(())
     }
 }
 
 #[cfg(test)]
 mod tests {
+    use super::*;
+
+    type Log = Vec<(u32, PhysicalInputValue)>;
+
+    /// Handler whose logical inputs `0..count` note what they were invoked with in the state.
+    fn logging_handler(count: u32) -> InputHandler<u32, Log> {
+        let mut handler = InputHandler::<u32, Log>::new();
+        for logical in 0..count {
+            handler.define(logical, move |log, _, value| {
+                log.push((logical, value));
+                Ok(())
+            });
+        }
+        handler
+    }
+
+    /// Logical inputs that were pressed, in order.
+    fn presses(log: &Log) -> Vec<u32> {
+        log.iter()
+            .filter(|&&(_, value)| value == PhysicalInputValue::Button(true))
+            .map(|&(logical, _)| logical)
+            .collect()
+    }
+
     #[test]
     fn sanity_check() {
         assert_eq!(2 + 2, 4);
     }
+
+    #[test]
+    fn rebinding() {
+        let mut handler = InputHandler::<u32, ()>::new();
+        handler
+            .bind(PhysicalInput::MButton(MouseButton::Left), 0)
+            .bind(PhysicalInput::MButton(MouseButton::Right), 0)
+            .bind(PhysicalInput::MButton(MouseButton::Right), 1);
+        assert_eq!(handler.bindings_for(&0).count(), 2);
+        assert_eq!(
+            handler
+                .logicals_for(&PhysicalInput::MButton(MouseButton::Right))
+                .collect::<Vec<_>>(),
+            vec![&0, &1]
+        );
+
+        handler.rebind(
+            1,
+            PhysicalInput::MButton(MouseButton::Right),
+            PhysicalInput::MButton(MouseButton::Middle),
+        );
+        assert_eq!(
+            handler.bindings_for(&1).collect::<Vec<_>>(),
+            vec![PhysicalInput::MButton(MouseButton::Middle)]
+        );
+        handler.rebind(
+            1,
+            PhysicalInput::MButton(MouseButton::Right),
+            PhysicalInput::MButton(MouseButton::X1),
+        );
+        assert_eq!(handler.bindings_for(&1).count(), 1);
+
+        handler.bind(PhysicalInput::MButton(MouseButton::Middle), 1);
+        assert_eq!(handler.bindings_for(&1).count(), 1);
+        assert_eq!(
+            handler
+                .logicals_for(&PhysicalInput::MButton(MouseButton::Middle))
+                .count(),
+            1
+        );
+
+        handler.unbind(PhysicalInput::MButton(MouseButton::Left), &0);
+        assert_eq!(
+            handler.bindings_for(&0).collect::<Vec<_>>(),
+            vec![PhysicalInput::MButton(MouseButton::Right)]
+        );
+
+        handler.unbind_all(&0);
+        assert_eq!(handler.bindings_for(&0).count(), 0);
+        assert_eq!(
+            handler
+                .logicals_for(&PhysicalInput::MButton(MouseButton::Right))
+                .count(),
+            0
+        );
+    }
+
+    #[test]
+    fn capture() {
+        let mut handler = InputHandler::<u32, ()>::new();
+        handler.begin_capture(0);
+        handler
+            .mouse_motion_event(&mut (), MouseState::from_sdl_state(0), 0, 0, 1, 1)
+            .unwrap();
+        handler
+            .controller_axis_event(&mut (), Axis::LeftX, 100, 0)
+            .unwrap();
+        handler
+            .key_down_event(&mut (), Keycode::A, Mod::empty(), true)
+            .unwrap();
+        assert!(handler.is_capturing());
+        handler
+            .key_down_event(&mut (), Keycode::A, Mod::empty(), false)
+            .unwrap();
+        assert!(!handler.is_capturing());
+        assert_eq!(
+            handler.take_capture_result(),
+            Some(CaptureResult::Bound(0, PhysicalInput::Key(Keycode::A)))
+        );
+        assert_eq!(handler.take_capture_result(), None);
+
+        handler.begin_capture(0);
+        handler
+            .key_down_event(&mut (), Keycode::Escape, Mod::empty(), false)
+            .unwrap();
+        assert_eq!(
+            handler.take_capture_result(),
+            Some(CaptureResult::Cancelled(0))
+        );
+        assert_eq!(
+            handler.bindings_for(&0).collect::<Vec<_>>(),
+            vec![PhysicalInput::Key(Keycode::A)]
+        );
+    }
+
+    #[test]
+    fn capture_releases() {
+        let mut handler = logging_handler(2);
+        let mut log = Log::new();
+        handler.bind(PhysicalInput::Key(Keycode::Escape), 1);
+
+        handler.begin_capture(0);
+        handler
+            .key_down_event(&mut log, Keycode::A, Mod::empty(), false)
+            .unwrap();
+        handler
+            .key_up_event(&mut log, Keycode::A, Mod::empty(), false)
+            .unwrap();
+        handler.begin_capture(0);
+        handler
+            .key_down_event(&mut log, Keycode::Escape, Mod::empty(), false)
+            .unwrap();
+        handler
+            .key_up_event(&mut log, Keycode::Escape, Mod::empty(), false)
+            .unwrap();
+        assert_eq!(log, vec![]);
+        assert_eq!(
+            handler.take_capture_result(),
+            Some(CaptureResult::Cancelled(0))
+        );
+
+        handler
+            .key_down_event(&mut log, Keycode::A, Mod::empty(), false)
+            .unwrap();
+        handler
+            .key_up_event(&mut log, Keycode::A, Mod::empty(), false)
+            .unwrap();
+        assert_eq!(
+            log,
+            vec![
+                (0, PhysicalInputValue::Button(true)),
+                (0, PhysicalInputValue::Button(false)),
+            ]
+        );
+
+        // Releasing what began the capture isn't swallowed.
+        log.clear();
+        handler
+            .key_down_event(&mut log, Keycode::Escape, Mod::empty(), false)
+            .unwrap();
+        handler.begin_capture(0);
+        handler
+            .key_up_event(&mut log, Keycode::Escape, Mod::empty(), false)
+            .unwrap();
+        assert!(handler.is_capturing());
+        assert!(!handler.is_down(&1));
+        assert_eq!(
+            log,
+            vec![
+                (1, PhysicalInputValue::Button(true)),
+                (1, PhysicalInputValue::Button(false)),
+            ]
+        );
+    }
+
+    #[test]
+    fn capture_axis_buttons() {
+        let mut handler = logging_handler(2);
+        let mut log = Log::new();
+        handler.bind(PhysicalInput::CAxisButton(0, Axis::LeftX, true), 1);
+
+        handler.begin_capture(0);
+        let max = i16::max_value();
+        for &raw_axis in &[max / 4 * 3, 0] {
+            handler
+                .controller_axis_event(&mut log, Axis::LeftX, raw_axis, 0)
+                .unwrap();
+        }
+        let axis = PhysicalInput::CAxis(0, Axis::LeftX);
+        assert_eq!(
+            handler.take_capture_result(),
+            Some(CaptureResult::Bound(0, axis))
+        );
+        assert!(presses(&log).is_empty() && !handler.is_down(&1));
+        handler
+            .controller_axis_event(&mut log, Axis::LeftX, max, 0)
+            .unwrap();
+        assert_eq!(presses(&log), vec![1]);
+    }
+
+    #[test]
+    fn context_stack() {
+        let mut handler = logging_handler(3);
+        let mut log = Log::new();
+        handler
+            .bind(PhysicalInput::Key(Keycode::A), 0)
+            .bind(PhysicalInput::Key(Keycode::B), 0);
+        handler
+            .define_context("vehicle", true)
+            .bind(PhysicalInput::Key(Keycode::A), 1);
+        handler
+            .define_context("menu", false)
+            .bind(PhysicalInput::Key(Keycode::C), 2);
+        assert!(handler.push_context("chat").is_err());
+
+        handler.push_context("vehicle").unwrap();
+        handler
+            .key_down_event(&mut log, Keycode::A, Mod::empty(), false)
+            .unwrap();
+        handler
+            .key_down_event(&mut log, Keycode::B, Mod::empty(), false)
+            .unwrap();
+        handler.push_context("menu").unwrap();
+        handler
+            .key_down_event(&mut log, Keycode::A, Mod::empty(), false)
+            .unwrap();
+        handler
+            .key_down_event(&mut log, Keycode::C, Mod::empty(), false)
+            .unwrap();
+        assert_eq!(handler.pop_context(), Some("menu".to_owned()));
+        handler.pop_context();
+        handler
+            .key_down_event(&mut log, Keycode::A, Mod::empty(), false)
+            .unwrap();
+        assert_eq!(presses(&log), vec![1, 0, 2, 0]);
+    }
+
+    #[test]
+    fn modifiers() {
+        use options::ModifierMatch;
+
+        let mut handler = logging_handler(3);
+        let mut log = Log::new();
+        let save = PhysicalInput::Key(Keycode::S);
+        handler
+            .bind_with(
+                save,
+                0,
+                BindingOptions::new().modifiers(ModifierMatch::Exact(Mod::empty())),
+            )
+            .bind_with(
+                save,
+                1,
+                BindingOptions::new().modifiers(ModifierMatch::Exact(Mod::LCTRLMOD)),
+            )
+            .bind_with(
+                save,
+                2,
+                BindingOptions::new()
+                    .modifiers(ModifierMatch::Subset(Mod::RCTRLMOD))
+                    .distinguish_sides(true),
+            );
+
+        handler
+            .key_down_event(&mut log, Keycode::S, Mod::NUMMOD, false)
+            .unwrap();
+        handler
+            .key_up_event(&mut log, Keycode::S, Mod::NUMMOD, false)
+            .unwrap();
+        handler
+            .key_down_event(&mut log, Keycode::S, Mod::RCTRLMOD, false)
+            .unwrap();
+        handler
+            .key_up_event(&mut log, Keycode::S, Mod::empty(), false)
+            .unwrap();
+        handler
+            .key_down_event(&mut log, Keycode::S, Mod::LCTRLMOD | Mod::LSHIFTMOD, false)
+            .unwrap();
+        handler
+            .key_up_event(&mut log, Keycode::S, Mod::empty(), false)
+            .unwrap();
+        assert_eq!(
+            log,
+            vec![
+                (0, PhysicalInputValue::Button(true)),
+                (0, PhysicalInputValue::Button(false)),
+                (1, PhysicalInputValue::Button(true)),
+                (2, PhysicalInputValue::Button(true)),
+                (1, PhysicalInputValue::Button(false)),
+                (2, PhysicalInputValue::Button(false)),
+            ]
+        );
+    }
+
+    #[test]
+    fn repeat_policy() {
+        use options::RepeatPolicy;
+
+        let mut handler = logging_handler(3);
+        let mut log = Log::new();
+        let key = PhysicalInput::Key(Keycode::Space);
+        handler
+            .bind(key, 0)
+            .bind_with(key, 1, BindingOptions::new().repeat(RepeatPolicy::Only))
+            .bind_with(key, 2, BindingOptions::new().repeat(RepeatPolicy::Both));
+
+        handler
+            .key_down_event(&mut log, Keycode::Space, Mod::empty(), false)
+            .unwrap();
+        handler
+            .key_down_event(&mut log, Keycode::Space, Mod::empty(), true)
+            .unwrap();
+        handler
+            .key_up_event(&mut log, Keycode::Space, Mod::empty(), false)
+            .unwrap();
+        assert_eq!(
+            log,
+            vec![
+                (0, PhysicalInputValue::Button(true)),
+                (2, PhysicalInputValue::Button(true)),
+                (1, PhysicalInputValue::Repeat),
+                (2, PhysicalInputValue::Repeat),
+                (0, PhysicalInputValue::Button(false)),
+                (1, PhysicalInputValue::Button(false)),
+                (2, PhysicalInputValue::Button(false)),
+            ]
+        );
+    }
+
+    #[test]
+    fn chords() {
+        let mut handler = logging_handler(4);
+        let mut log = Log::new();
+        let lb = PhysicalInput::CButton(0, Button::LeftShoulder);
+        let rb = PhysicalInput::CButton(0, Button::RightShoulder);
+        let shift = PhysicalInput::Key(Keycode::LShift);
+        let mouse = PhysicalInput::MButton(MouseButton::Left);
+        handler
+            .bind(lb, 0)
+            .bind(shift, 1)
+            .bind_chord(&[lb, rb], 2, false)
+            .bind_chord(&[shift, mouse], 3, true);
+
+        handler
+            .controller_button_down_event(&mut log, Button::LeftShoulder, 0)
+            .unwrap();
+        handler
+            .controller_button_down_event(&mut log, Button::RightShoulder, 0)
+            .unwrap();
+        handler
+            .controller_button_up_event(&mut log, Button::LeftShoulder, 0)
+            .unwrap();
+        handler
+            .controller_button_up_event(&mut log, Button::RightShoulder, 0)
+            .unwrap();
+        handler
+            .key_down_event(&mut log, Keycode::LShift, Mod::LSHIFTMOD, false)
+            .unwrap();
+        handler
+            .mouse_button_down_event(&mut log, MouseButton::Left, 0, 0)
+            .unwrap();
+        handler
+            .key_down_event(&mut log, Keycode::LShift, Mod::LSHIFTMOD, true)
+            .unwrap();
+        handler
+            .mouse_button_up_event(&mut log, MouseButton::Left, 0, 0)
+            .unwrap();
+        handler
+            .key_up_event(&mut log, Keycode::LShift, Mod::empty(), false)
+            .unwrap();
+        assert_eq!(
+            log,
+            vec![
+                (0, PhysicalInputValue::Button(true)),
+                (2, PhysicalInputValue::Button(true)),
+                (0, PhysicalInputValue::Button(false)),
+                (2, PhysicalInputValue::Button(false)),
+                (1, PhysicalInputValue::Button(true)),
+                (1, PhysicalInputValue::Button(false)),
+                (3, PhysicalInputValue::Button(true)),
+                (3, PhysicalInputValue::Button(false)),
+            ]
+        );
+    }
+
+    #[test]
+    fn sequences() {
+        let mut handler = logging_handler(3);
+        let mut log = Log::new();
+        let key = |keycode| SequenceStep::Physical(PhysicalInput::Key(keycode));
+        handler
+            .bind(PhysicalInput::CButton(0, Button::X), 0)
+            .bind_sequence(
+                vec![
+                    key(Keycode::Down),
+                    key(Keycode::Right),
+                    SequenceStep::Logical(0),
+                ],
+                1,
+                SequenceOptions::new().max_gap(0.2).window(0.5),
+            )
+            .bind_sequence(
+                vec![key(Keycode::A), key(Keycode::B)],
+                2,
+                SequenceOptions::new().allow_interleaved(true),
+            );
+
+        let press_at =
+            |handler: &mut InputHandler<u32, Log>, log: &mut Log, time, physical, value| {
+                handler.set_time(time);
+                handler
+                    .resolve_and_invoke(log, physical, PhysicalInputValue::Button(value))
+                    .unwrap();
+            };
+        let down = PhysicalInput::Key(Keycode::Down);
+        let right = PhysicalInput::Key(Keycode::Right);
+        let x = PhysicalInput::CButton(0, Button::X);
+        // Completed in time.
+        press_at(&mut handler, &mut log, 0.0, down, true);
+        press_at(&mut handler, &mut log, 0.1, right, true);
+        press_at(&mut handler, &mut log, 0.15, right, false);
+        press_at(&mut handler, &mut log, 0.25, x, true);
+        assert_eq!(presses(&log), vec![0, 1]);
+        // Gap too long.
+        press_at(&mut handler, &mut log, 1.0, down, true);
+        press_at(&mut handler, &mut log, 1.3, right, true);
+        press_at(&mut handler, &mut log, 1.4, x, true);
+        // Interleaved.
+        press_at(&mut handler, &mut log, 2.0, down, true);
+        press_at(&mut handler, &mut log, 2.1, x, true);
+        press_at(&mut handler, &mut log, 2.2, right, true);
+        press_at(&mut handler, &mut log, 2.3, x, true);
+        assert_eq!(presses(&log), vec![0, 1, 0, 0, 0]);
+
+        press_at(
+            &mut handler,
+            &mut log,
+            3.0,
+            PhysicalInput::Key(Keycode::A),
+            true,
+        );
+        press_at(&mut handler, &mut log, 3.1, down, true);
+        press_at(
+            &mut handler,
+            &mut log,
+            3.2,
+            PhysicalInput::Key(Keycode::B),
+            true,
+        );
+        assert_eq!(presses(&log), vec![0, 1, 0, 0, 0, 2]);
+    }
+
+    #[test]
+    fn interactions() {
+        let mut handler = logging_handler(3);
+        let mut log = Log::new();
+        let key = PhysicalInput::Key(Keycode::E);
+        handler
+            .bind_with(
+                key,
+                0,
+                BindingOptions::new().interaction(Interaction::Tap { max_duration: 0.2 }),
+            )
+            .bind_with(
+                key,
+                1,
+                BindingOptions::new().interaction(Interaction::Hold { duration: 1.0 }),
+            )
+            .bind_with(
+                key,
+                2,
+                BindingOptions::new().interaction(Interaction::double_tap(0.3)),
+            );
+        let press = PhysicalInputValue::Button(true);
+        let release = PhysicalInputValue::Button(false);
+
+        handler.resolve_and_invoke(&mut log, key, press).unwrap();
+        handler.update(&mut log, 0.1).unwrap();
+        handler.resolve_and_invoke(&mut log, key, release).unwrap();
+        handler.update(&mut log, 0.1).unwrap();
+        handler.resolve_and_invoke(&mut log, key, press).unwrap();
+        assert_eq!(log, vec![(0, press), (0, release), (2, press)]);
+        log.clear();
+
+        handler.update(&mut log, 0.5).unwrap();
+        handler.update(&mut log, 0.6).unwrap();
+        handler.update(&mut log, 0.1).unwrap();
+        handler.resolve_and_invoke(&mut log, key, release).unwrap();
+        assert_eq!(log, vec![(1, press), (1, release), (2, release)]);
+    }
+
+    #[test]
+    fn simultaneous_holds() {
+        let mut handler = logging_handler(4);
+        let mut log = Log::new();
+        let hold = BindingOptions::new().interaction(Interaction::Hold { duration: 1.0 });
+        let keys = [
+            PhysicalInput::Key(Keycode::A),
+            PhysicalInput::Key(Keycode::B),
+            PhysicalInput::Key(Keycode::C),
+        ];
+        handler
+            .bind_with(keys[0], 0, hold.clone())
+            .bind_with(keys[0], 1, hold.clone().priority(1))
+            .bind_with(keys[1], 2, hold.clone())
+            .bind_with(keys[2], 3, hold);
+        let press = PhysicalInputValue::Button(true);
+
+        for &key in keys.iter().rev() {
+            handler.resolve_and_invoke(&mut log, key, press).unwrap();
+        }
+        handler.update(&mut log, 1.0).unwrap();
+        assert_eq!(log, vec![(1, press), (3, press), (2, press), (0, press)]);
+    }
+
+    #[test]
+    fn polling() {
+        let mut handler = InputHandler::<u32, ()>::new();
+        handler
+            .bind(PhysicalInput::Key(Keycode::Space), 0)
+            .bind(PhysicalInput::CButton(0, Button::A), 0)
+            .bind(PhysicalInput::CAxis(0, Axis::LeftX), 1);
+
+        handler
+            .key_down_event(&mut (), Keycode::Space, Mod::empty(), false)
+            .unwrap();
+        handler
+            .controller_button_down_event(&mut (), Button::A, 0)
+            .unwrap();
+        handler
+            .controller_axis_event(&mut (), Axis::LeftX, i16::min_value(), 0)
+            .unwrap();
+        assert!(handler.is_down(&0) && handler.just_pressed(&0));
+        assert_eq!(handler.value_f32(&1), -1.0);
+        handler.end_frame();
+
+        handler
+            .key_up_event(&mut (), Keycode::Space, Mod::empty(), false)
+            .unwrap();
+        assert!(handler.is_down(&0) && !handler.just_pressed(&0));
+        handler
+            .controller_button_up_event(&mut (), Button::A, 0)
+            .unwrap();
+        assert!(!handler.is_down(&0) && handler.just_released(&0));
+        handler.end_frame();
+        assert!(!handler.just_released(&0));
+        assert!(!handler.is_down(&2));
+    }
+
+    #[test]
+    fn axis_normalization() {
+        use options::AxisNormalization;
+
+        let mut handler = InputHandler::<u32, ()>::new();
+        handler
+            .bind_with(
+                PhysicalInput::CAxis(0, Axis::TriggerLeft),
+                0,
+                BindingOptions::new().normalization(AxisNormalization::trigger()),
+            )
+            .bind_with(
+                PhysicalInput::CAxis(0, Axis::TriggerLeft),
+                1,
+                BindingOptions::new().normalization(AxisNormalization::trigger().inverted(true)),
+            );
+
+        handler
+            .controller_axis_event(&mut (), Axis::TriggerLeft, i16::max_value() / 4, 0)
+            .unwrap();
+        assert!((handler.value_f32(&0) - 0.25).abs() < 1e-4);
+        assert!((handler.value_f32(&1) - 0.75).abs() < 1e-4);
+        handler
+            .controller_axis_event(&mut (), Axis::TriggerLeft, -5, 0)
+            .unwrap();
+        assert_eq!(handler.value_f32(&0), 0.0);
+        assert_eq!(handler.value_f32(&1), 1.0);
+    }
+
+    #[test]
+    fn axis_buttons() {
+        use options::AxisNormalization;
+
+        let mut handler = logging_handler(2);
+        let mut log = Log::new();
+        handler
+            .bind(PhysicalInput::CAxisButton(0, Axis::LeftX, true), 0)
+            .bind(PhysicalInput::CAxisButton(0, Axis::LeftX, false), 1)
+            .set_axis_threshold(Axis::LeftX, AxisThreshold::new(0.5, 0.25));
+
+        let max = i16::max_value();
+        for &raw_axis in &[max / 8 * 5, max / 8 * 3, max / 8 * 5, max / 8, -max, max] {
+            handler
+                .controller_axis_event(&mut log, Axis::LeftX, raw_axis, 0)
+                .unwrap();
+        }
+        assert_eq!(
+            log,
+            vec![
+                (0, PhysicalInputValue::Button(true)),
+                (0, PhysicalInputValue::Button(false)),
+                (1, PhysicalInputValue::Button(true)),
+                (1, PhysicalInputValue::Button(false)),
+                (0, PhysicalInputValue::Button(true)),
+            ]
+        );
+
+        // A trigger resting at the bottom of its range doesn't press the negative half.
+        log.clear();
+        handler
+            .bind(PhysicalInput::CAxisButton(0, Axis::TriggerLeft, true), 0)
+            .bind(PhysicalInput::CAxisButton(0, Axis::TriggerLeft, false), 1)
+            .set_axis_threshold(
+                Axis::TriggerLeft,
+                AxisThreshold::new(0.5, 0.25).normalization(AxisNormalization::trigger()),
+            );
+        for &raw_axis in &[-max, max / 4 * 3, 0] {
+            handler
+                .controller_axis_event(&mut log, Axis::TriggerLeft, raw_axis, 0)
+                .unwrap();
+        }
+        assert_eq!(
+            log,
+            vec![
+                (0, PhysicalInputValue::Button(true)),
+                (0, PhysicalInputValue::Button(false)),
+            ]
+        );
+    }
+
+    #[test]
+    fn mouse_wheel() {
+        use options::WheelMode;
+
+        let mut handler = logging_handler(2);
+        let mut log = Log::new();
+        handler.bind(PhysicalInput::MWheelY(false), 0).bind_with(
+            PhysicalInput::MWheelY(false),
+            1,
+            BindingOptions::new().wheel(WheelMode::Delta),
+        );
+
+        handler.mouse_wheel_event(&mut log, 0, -2).unwrap();
+        handler.mouse_wheel_event(&mut log, 0, 1).unwrap();
+        let press = PhysicalInputValue::Button(true);
+        let release = PhysicalInputValue::Button(false);
+        assert_eq!(
+            log,
+            vec![
+                (0, press),
+                (0, release),
+                (0, press),
+                (0, release),
+                (1, PhysicalInputValue::Wheel(-2)),
+            ]
+        );
+        assert!(!handler.is_down(&0) && handler.just_released(&0));
+        assert_eq!(handler.value_f32(&1), -2.0);
+        handler.end_frame();
+        assert_eq!(handler.value_f32(&1), 0.0);
+    }
+
+    #[test]
+    fn error_policies() {
+        use error::{ErrorPolicy, InputtyError};
+        use std::cell::RefCell;
+        use std::error::Error;
+        use std::rc::Rc;
+
+        let released = Rc::new(RefCell::new(Vec::new()));
+        let mut handler = InputHandler::<u32, ()>::new();
+        for &logical in &[0, 1] {
+            let released = released.clone();
+            handler.define(logical, move |_, _, value| match value {
+                PhysicalInputValue::Button(true) => {
+                    Err(InputtyError::new(format!("failed {}", logical)))
+                }
+                _ => {
+                    released.borrow_mut().push(logical);
+                    Ok(())
+                }
+            });
+        }
+        handler
+            .bind(PhysicalInput::Key(Keycode::Space), 0)
+            .bind(PhysicalInput::Key(Keycode::Space), 1);
+        let press = |handler: &mut InputHandler<u32, ()>| {
+            let result = handler.key_down_event(&mut (), Keycode::Space, Mod::empty(), false);
+            handler
+                .key_up_event(&mut (), Keycode::Space, Mod::empty(), false)
+                .unwrap();
+            result
+        };
+
+        press(&mut handler).unwrap();
+        assert_eq!(handler.drain_errors().count(), 0);
+
+        handler.set_error_policy(ErrorPolicy::Queue);
+        press(&mut handler).unwrap();
+        let messages = handler
+            .drain_errors()
+            .map(|error| error.error.to_string())
+            .collect::<Vec<_>>();
+        assert_eq!(messages, vec!["failed 0", "failed 1"]);
+
+        let hooked = Rc::new(RefCell::new(Vec::new()));
+        let hook = hooked.clone();
+        handler.set_error_policy(ErrorPolicy::Hook(Box::new(move |error| {
+            hook.borrow_mut().push(error.logical)
+        })));
+        press(&mut handler).unwrap();
+        assert_eq!(*hooked.borrow(), vec![0, 1]);
+
+        handler.set_error_policy(ErrorPolicy::Abort);
+        released.borrow_mut().clear();
+        let error = press(&mut handler).unwrap_err();
+        assert_eq!(*released.borrow(), vec![0]);
+        assert_eq!(error.logical, 0);
+        assert_eq!(error.value, PhysicalInputValue::Button(true));
+        assert_eq!(error.error.message(), "failed 0");
+        assert_eq!(error.source().unwrap().to_string(), "failed 0");
+    }
+
+    #[test]
+    fn propagation() {
+        use std::cell::Cell;
+        use std::rc::Rc;
+
+        let over_ui = Rc::new(Cell::new(true));
+        let mut handler = logging_handler(3);
+        let mut log = Log::new();
+        {
+            let over_ui = over_ui.clone();
+            handler.define_propagating(3, move |log, _, value| {
+                log.push((3, value));
+                if over_ui.get() {
+                    Ok(Propagation::Stop)
+                } else {
+                    Ok(Propagation::Continue)
+                }
+            });
+        }
+        let button = PhysicalInput::MButton(MouseButton::Left);
+        handler
+            .bind(button, 0)
+            .bind_with(button, 1, BindingOptions::new().priority(-1))
+            .bind(button, 2)
+            .bind(button, 3)
+            .set_priority(3, 10)
+            .set_priority(2, 5);
+
+        let click = |handler: &mut InputHandler<u32, Log>, log: &mut Log| {
+            handler
+                .mouse_button_down_event(log, MouseButton::Left, 0, 0)
+                .unwrap();
+            handler
+                .mouse_button_up_event(log, MouseButton::Left, 0, 0)
+                .unwrap();
+        };
+        let press = PhysicalInputValue::Button(true);
+        let release = PhysicalInputValue::Button(false);
+
+        click(&mut handler, &mut log);
+        assert_eq!(log, vec![(3, press), (3, release)]);
+        assert!(!handler.just_pressed(&0));
+
+        log.clear();
+        over_ui.set(false);
+        click(&mut handler, &mut log);
+        let unstopped = vec![
+            (3, press),
+            (2, press),
+            (0, press),
+            (1, press),
+            (3, release),
+            (2, release),
+            (0, release),
+            (1, release),
+        ];
+        assert_eq!(log, unstopped);
+
+        // What a stopped press didn't reach doesn't get its release either, and stopping a
+        // release doesn't leave what the press reached held.
+        log.clear();
+        over_ui.set(true);
+        handler
+            .mouse_button_down_event(&mut log, MouseButton::Left, 0, 0)
+            .unwrap();
+        over_ui.set(false);
+        handler
+            .mouse_button_up_event(&mut log, MouseButton::Left, 0, 0)
+            .unwrap();
+        assert_eq!(log, vec![(3, press), (3, release)]);
+
+        log.clear();
+        handler
+            .mouse_button_down_event(&mut log, MouseButton::Left, 0, 0)
+            .unwrap();
+        over_ui.set(true);
+        handler
+            .mouse_button_up_event(&mut log, MouseButton::Left, 0, 0)
+            .unwrap();
+        assert_eq!(log, unstopped);
+        assert!((0..4).all(|logical| !handler.is_down(&logical)));
+    }
+
+    #[test]
+    fn stopped_repeats_and_taps() {
+        use options::RepeatPolicy;
+
+        let mut handler = logging_handler(2);
+        let mut log = Log::new();
+        handler
+            .define_propagating(0, |log, _, value| {
+                log.push((0, value));
+                if value == PhysicalInputValue::Repeat {
+                    Ok(Propagation::Stop)
+                } else {
+                    Ok(Propagation::Continue)
+                }
+            })
+            .bind_with(
+                PhysicalInput::Key(Keycode::Space),
+                0,
+                BindingOptions::new().repeat(RepeatPolicy::Both).priority(1),
+            )
+            .bind_with(
+                PhysicalInput::Key(Keycode::Space),
+                1,
+                BindingOptions::new().repeat(RepeatPolicy::Both),
+            );
+        let press = PhysicalInputValue::Button(true);
+        let release = PhysicalInputValue::Button(false);
+
+        // A stopped repeat doesn't keep the release from what the press reached.
+        handler
+            .key_down_event(&mut log, Keycode::Space, Mod::empty(), false)
+            .unwrap();
+        handler
+            .key_down_event(&mut log, Keycode::Space, Mod::empty(), true)
+            .unwrap();
+        handler
+            .key_up_event(&mut log, Keycode::Space, Mod::empty(), false)
+            .unwrap();
+        assert_eq!(
+            log,
+            vec![
+                (0, press),
+                (1, press),
+                (0, PhysicalInputValue::Repeat),
+                (0, release),
+                (1, release),
+            ]
+        );
+        assert!(!handler.is_down(&1));
+
+        // Nor does stopping the press a tap makes of a release.
+        let mut handler = logging_handler(2);
+        let mut log = Log::new();
+        handler
+            .define_propagating(0, |log, _, value| {
+                log.push((0, value));
+                Ok(Propagation::Stop)
+            })
+            .bind_with(
+                PhysicalInput::Key(Keycode::Space),
+                0,
+                BindingOptions::new()
+                    .interaction(Interaction::Tap { max_duration: 0.5 })
+                    .priority(1),
+            )
+            .bind(PhysicalInput::Key(Keycode::Space), 1);
+        handler
+            .key_down_event(&mut log, Keycode::Space, Mod::empty(), false)
+            .unwrap();
+        handler
+            .key_up_event(&mut log, Keycode::Space, Mod::empty(), false)
+            .unwrap();
+        assert_eq!(
+            log,
+            vec![(1, press), (0, press), (0, release), (1, release)]
+        );
+        assert!(!handler.is_down(&0) && !handler.is_down(&1));
+    }
+
+    #[test]
+    fn queued_dispatch() {
+        use event::{DispatchMode, LogicalEvent};
+
+        let mut handler = InputHandler::<u32, ()>::new();
+        handler
+            .define(0, |_, _, _| Err("callbacks aren't invoked".into()))
+            .set_error_policy(ErrorPolicy::Abort)
+            .set_dispatch_mode(DispatchMode::Queued)
+            .bind(PhysicalInput::Key(Keycode::Space), 0)
+            .bind(PhysicalInput::Key(Keycode::Space), 1);
+
+        handler.set_time(1.5);
+        handler
+            .key_down_event(&mut (), Keycode::Space, Mod::empty(), false)
+            .unwrap();
+        let events = handler.drain_events().collect::<Vec<_>>();
+        assert_eq!(
+            events,
+            vec![
+                LogicalEvent {
+                    logical: 0,
+                    physical: PhysicalInput::Key(Keycode::Space),
+                    value: PhysicalInputValue::Button(true),
+                    timestamp: 1.5,
+                },
+                LogicalEvent {
+                    logical: 1,
+                    physical: PhysicalInput::Key(Keycode::Space),
+                    value: PhysicalInputValue::Button(true),
+                    timestamp: 1.5,
+                },
+            ]
+        );
+        assert!(handler.is_down(&1));
+        assert_eq!(handler.drain_events().count(), 0);
+
+        handler.set_dispatch_mode(DispatchMode::Both);
+        assert!(handler
+            .key_up_event(&mut (), Keycode::Space, Mod::empty(), false)
+            .is_err());
+        assert_eq!(handler.drain_events().count(), 1);
+    }
+
+    #[test]
+    fn contextual_callbacks_headless() {
+        let mut handler = logging_handler(2);
+        let mut log = Log::new();
+        handler
+            .define_with_context(0, |_, _, _, _| Err("needs a context".into()))
+            .set_error_policy(ErrorPolicy::Abort)
+            .bind(PhysicalInput::Key(Keycode::Space), 0)
+            .bind(PhysicalInput::Key(Keycode::Space), 1);
+
+        handler
+            .key_down_event(&mut log, Keycode::Space, Mod::empty(), false)
+            .unwrap();
+        handler
+            .key_up_event(&mut log, Keycode::Space, Mod::empty(), false)
+            .unwrap();
+        assert_eq!(
+            log,
+            vec![
+                (1, PhysicalInputValue::Button(true)),
+                (1, PhysicalInputValue::Button(false)),
+            ]
+        );
+        assert!(!handler.is_down(&0));
+    }
+
+    #[test]
+    fn recording() {
+        let mut handler = InputHandler::<u32, ()>::new();
+        handler.bind(PhysicalInput::Key(Keycode::Space), 0);
+        handler
+            .key_down_event(&mut (), Keycode::Space, Mod::empty(), false)
+            .unwrap();
+        handler.update(&mut (), 0.5).unwrap();
+        handler.start_recording();
+        assert!(handler.is_recording());
+        handler
+            .key_up_event(&mut (), Keycode::Space, Mod::LSHIFTMOD, false)
+            .unwrap();
+        handler.update(&mut (), 0.25).unwrap();
+        handler
+            .controller_axis_event(&mut (), Axis::LeftX, i16::max_value(), 0)
+            .unwrap();
+        let recording = handler.stop_recording().unwrap();
+        assert!(!handler.is_recording());
+        handler.mouse_wheel_event(&mut (), 0, 1).unwrap();
+
+        let events = recording
+            .events
+            .iter()
+            .map(|event| {
+                (
+                    event.frame,
+                    event.timestamp,
+                    event.physical,
+                    event.value,
+                    event.keymod,
+                )
+            })
+            .collect::<Vec<_>>();
+        assert_eq!(
+            events,
+            vec![
+                (
+                    0,
+                    0.0,
+                    PhysicalInput::Key(Keycode::Space),
+                    PhysicalInputValue::Button(false),
+                    Mod::LSHIFTMOD,
+                ),
+                (
+                    1,
+                    0.25,
+                    PhysicalInput::CAxis(0, Axis::LeftX),
+                    PhysicalInputValue::Axis(i16::max_value()),
+                    Mod::LSHIFTMOD,
+                ),
+                (
+                    1,
+                    0.25,
+                    PhysicalInput::CAxisButton(0, Axis::LeftX, true),
+                    PhysicalInputValue::Button(true),
+                    Mod::LSHIFTMOD,
+                ),
+            ]
+        );
+        assert!(handler.stop_recording().is_none());
+    }
 }
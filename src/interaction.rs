@@ -0,0 +1,121 @@
+use input_handler::PhysicalInputValue;
+
+/// Decides when a binding presses and releases its logical input; times are in seconds of
+/// `InputHandler` time. Only button-like values are affected, others are passed through.
+#[derive(PartialEq, Copy, Clone, Debug)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
+pub enum Interaction {
+    /// Presses and releases are passed through as they are.
+    Press,
+    /// Pressed and immediately released on release, if it was held for at most `max_duration`.
+    Tap { max_duration: f32 },
+    /// Pressed once held for `duration`, as noticed by `InputHandler::update()`; released on
+    /// release.
+    Hold { duration: f32 },
+    /// Pressed on the `count`th consecutive press, each following the previous one within
+    /// `max_gap`; released on release.
+    MultiTap { count: u32, max_gap: f32 },
+}
+
+impl Default for Interaction {
+    fn default() -> Self {
+        Interaction::Press
+    }
+}
+
+/// Per-binding bookkeeping of an interaction.
+#[derive(Default)]
+pub(crate) struct InteractionState {
+    pressed_at: Option<f32>,
+    fired: bool,
+    taps: u32,
+    last_tap: f32,
+}
+
+impl Interaction {
+    pub fn double_tap(max_gap: f32) -> Self {
+        Interaction::MultiTap { count: 2, max_gap }
+    }
+
+    /// Tracks given physical input value; returns values the logical input should receive.
+    pub(crate) fn process(
+        &self,
+        state: &mut InteractionState,
+        value: PhysicalInputValue,
+        now: f32,
+    ) -> Vec<PhysicalInputValue> {
+        let down = match value {
+            PhysicalInputValue::Button(down) => down,
+            PhysicalInputValue::Repeat => return Vec::new(),
+            _ => return vec![value],
+        };
+        match *self {
+            Interaction::Press => vec![value],
+            Interaction::Tap { max_duration } => {
+                if down {
+                    state.pressed_at = Some(now);
+                    return Vec::new();
+                }
+                match state.pressed_at.take() {
+                    Some(pressed_at) if now - pressed_at <= max_duration => vec![
+                        PhysicalInputValue::Button(true),
+                        PhysicalInputValue::Button(false),
+                    ],
+                    _ => Vec::new(),
+                }
+            }
+            Interaction::Hold { .. } => {
+                if down {
+                    state.pressed_at = Some(now);
+                    state.fired = false;
+                    return Vec::new();
+                }
+                self.cancel(state).into_iter().collect()
+            }
+            Interaction::MultiTap { count, max_gap } => {
+                if !down {
+                    return self.cancel(state).into_iter().collect();
+                }
+                if state.taps > 0 && now - state.last_tap > max_gap {
+                    state.taps = 0;
+                }
+                state.taps += 1;
+                state.last_tap = now;
+                if state.taps >= count {
+                    state.taps = 0;
+                    state.fired = true;
+                    return vec![PhysicalInputValue::Button(true)];
+                }
+                Vec::new()
+            }
+        }
+    }
+
+    /// Advances time-driven interactions; returns a value the logical input should receive.
+    pub(crate) fn tick(
+        &self,
+        state: &mut InteractionState,
+        now: f32,
+    ) -> Option<PhysicalInputValue> {
+        if let Interaction::Hold { duration } = *self {
+            if let Some(pressed_at) = state.pressed_at {
+                if !state.fired && now - pressed_at >= duration {
+                    state.fired = true;
+                    return Some(PhysicalInputValue::Button(true));
+                }
+            }
+        }
+        None
+    }
+
+    /// Ends an ongoing press without completing it; returns a release if a press was sent.
+    pub(crate) fn cancel(&self, state: &mut InteractionState) -> Option<PhysicalInputValue> {
+        state.pressed_at = None;
+        if state.fired {
+            state.fired = false;
+            Some(PhysicalInputValue::Button(false))
+        } else {
+            None
+        }
+    }
+}
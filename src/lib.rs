@@ -1,13 +1,51 @@
 extern crate ggez;
 #[macro_use]
 extern crate log;
+#[cfg(feature = "serde")]
+#[macro_use]
+extern crate serde;
+#[cfg(all(test, feature = "serde"))]
+extern crate serde_json;
 
+pub mod capture;
+mod chord;
+mod context;
+mod error;
+mod event;
 mod input_handler;
+mod interaction;
+mod logical_state;
 mod macros;
+mod options;
+#[cfg(feature = "serde")]
+pub mod profile;
+pub mod recording;
+pub mod replay;
+pub mod sequence;
 pub mod virtual_axis;
+pub mod virtual_stick;
 
+pub use context::BindingContext;
+pub use error::CallbackError;
+pub use error::DispatchResult;
+pub use error::ErrorPolicy;
+pub use error::InputtyError;
+#[doc(hidden)]
+pub use error::log_dispatch_error;
+pub use event::DispatchMode;
+pub use event::LogicalEvent;
 pub use input_handler::InputHandler;
 pub use input_handler::InputHandlerDefGen;
 pub use input_handler::InputtyResult;
 pub use input_handler::PhysicalInput;
 pub use input_handler::PhysicalInputValue;
+pub use input_handler::Propagation;
+pub use input_handler::PropagationResult;
+pub use interaction::Interaction;
+pub use logical_state::LogicalInputState;
+pub use options::AxisNormalization;
+pub use options::AxisThreshold;
+pub use options::BindingOptions;
+pub use options::ModifierMatch;
+pub use options::RepeatPolicy;
+pub use options::WheelMode;
@@ -0,0 +1,75 @@
+use input_handler::PhysicalInputValue;
+
+/// Pollable state of a logical input, as of the last `InputHandler::end_frame()`.
+#[derive(PartialEq, Copy, Clone, Debug, Default)]
+pub struct LogicalInputState {
+    /// Number of physical inputs currently holding it down.
+    held: u32,
+    just_pressed: bool,
+    just_released: bool,
+    value: f32,
+    /// Whether the value is a wheel delta, which only lasts until the end of frame.
+    wheel: bool,
+}
+
+impl LogicalInputState {
+    pub fn is_down(&self) -> bool {
+        self.held > 0
+    }
+
+    /// Whether it went down since the last end of frame.
+    pub fn just_pressed(&self) -> bool {
+        self.just_pressed
+    }
+
+    /// Whether it went up since the last end of frame.
+    pub fn just_released(&self) -> bool {
+        self.just_released
+    }
+
+    /// Last value, normalized: 1.0 or 0.0 for buttons, -1.0 to 1.0 for axes; wheel deltas are
+    /// kept as they are, and reset to 0.0 at the end of frame.
+    pub fn value_f32(&self) -> f32 {
+        self.value
+    }
+
+    pub(crate) fn dispatch(&mut self, value: PhysicalInputValue) {
+        match value {
+            PhysicalInputValue::Button(true) => {
+                if self.held == 0 {
+                    self.just_pressed = true;
+                }
+                self.held += 1;
+                self.value = 1.0;
+                self.wheel = false;
+            }
+            PhysicalInputValue::Button(false) => {
+                if self.held > 0 {
+                    self.held -= 1;
+                    if self.held == 0 {
+                        self.just_released = true;
+                        self.value = 0.0;
+                    }
+                }
+            }
+            PhysicalInputValue::Axis(raw_axis) => {
+                self.value = (raw_axis as f32 / i16::max_value() as f32).max(-1.0);
+                self.wheel = false;
+            }
+            PhysicalInputValue::Wheel(delta) => {
+                self.value = delta as f32;
+                self.wheel = true;
+            }
+            PhysicalInputValue::Repeat | PhysicalInputValue::XY(..) => (),
+        }
+    }
+
+    pub(crate) fn end_frame(&mut self) {
+        self.just_pressed = false;
+        self.just_released = false;
+        if self.wheel {
+            self.wheel = false;
+            self.value = 0.0;
+        }
+    }
+}
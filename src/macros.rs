@@ -1,94 +1,149 @@
-/// Generates implementations of input-related methods of `ggez::event::EventHandler`.
+/// Generates implementations of input-related methods of `ggez::event::EventHandler`, passing
+/// `ggez::Context` on to callbacks defined with `define_with_context()`; errors returned under
+/// `ErrorPolicy::Abort` are logged with `error!`, as these methods can't return them.
 #[macro_export]
 macro_rules! impl_input_handling {
     ($handler:ident, $state:ident) => {
         fn mouse_button_down_event(
             &mut self,
-            _ctx: &mut ggez::Context,
+            ctx: &mut ggez::Context,
             button: ggez::event::MouseButton,
             x: i32,
             y: i32,
         ) {
-            self.$handler.mouse_button_down_event(&mut self.$state, button, x, y);
+            $crate::log_dispatch_error(self.$handler.mouse_button_down_event_with_context(
+                ctx,
+                &mut self.$state,
+                button,
+                x,
+                y,
+            ));
         }
 
         fn mouse_button_up_event(
             &mut self,
-            _ctx: &mut ggez::Context,
+            ctx: &mut ggez::Context,
             button: ggez::event::MouseButton,
             x: i32,
             y: i32,
         ) {
-            self.$handler.mouse_button_up_event(&mut self.$state, button, x, y);
+            $crate::log_dispatch_error(self.$handler.mouse_button_up_event_with_context(
+                ctx,
+                &mut self.$state,
+                button,
+                x,
+                y,
+            ));
         }
 
         fn mouse_motion_event(
             &mut self,
-            _ctx: &mut ggez::Context,
+            ctx: &mut ggez::Context,
             state: ggez::event::MouseState,
             x: i32,
             y: i32,
             xrel: i32,
             yrel: i32,
         ) {
-            self.$handler.mouse_motion_event(&mut self.$state, state, x, y, xrel, yrel);
+            $crate::log_dispatch_error(self.$handler.mouse_motion_event_with_context(
+                ctx,
+                &mut self.$state,
+                state,
+                x,
+                y,
+                xrel,
+                yrel,
+            ));
         }
 
         fn mouse_wheel_event(
             &mut self,
-            _ctx: &mut ggez::Context,
+            ctx: &mut ggez::Context,
             x: i32,
             y: i32,
         ) {
-            self.$handler.mouse_wheel_event(&mut self.$state, x, y);
+            $crate::log_dispatch_error(self.$handler.mouse_wheel_event_with_context(
+                ctx,
+                &mut self.$state,
+                x,
+                y,
+            ));
         }
 
         fn key_down_event(
             &mut self,
-            _ctx: &mut ggez::Context,
+            ctx: &mut ggez::Context,
             keycode: ggez::event::Keycode,
             keymod: ggez::event::Mod,
             repeat: bool,
         ) {
-            self.$handler.key_down_event(&mut self.$state, keycode, keymod, repeat);
+            $crate::log_dispatch_error(self.$handler.key_down_event_with_context(
+                ctx,
+                &mut self.$state,
+                keycode,
+                keymod,
+                repeat,
+            ));
         }
 
         fn key_up_event(
             &mut self,
-            _ctx: &mut ggez::Context,
+            ctx: &mut ggez::Context,
             keycode: ggez::event::Keycode,
             keymod: ggez::event::Mod,
             repeat: bool,
         ) {
-            self.$handler.key_up_event(&mut self.$state, keycode, keymod, repeat);
+            $crate::log_dispatch_error(self.$handler.key_up_event_with_context(
+                ctx,
+                &mut self.$state,
+                keycode,
+                keymod,
+                repeat,
+            ));
         }
 
         fn controller_button_down_event(
             &mut self,
-            _ctx: &mut ggez::Context,
+            ctx: &mut ggez::Context,
             button: ggez::event::Button,
             instance_id: i32,
         ) {
-            self.$handler.controller_button_down_event(&mut self.$state, button, instance_id);
+            $crate::log_dispatch_error(self.$handler.controller_button_down_event_with_context(
+                ctx,
+                &mut self.$state,
+                button,
+                instance_id,
+            ));
         }
 
         fn controller_button_up_event(
             &mut self,
-            _ctx: &mut ggez::Context,
+            ctx: &mut ggez::Context,
             button: ggez::event::Button,
             instance_id: i32,
         ) {
-            self.$handler.controller_button_up_event(&mut self.$state, button, instance_id);
+            $crate::log_dispatch_error(self.$handler.controller_button_up_event_with_context(
+                ctx,
+                &mut self.$state,
+                button,
+                instance_id,
+            ));
         }
 
         fn controller_axis_event(
             &mut self,
-            _ctx: &mut ggez::Context,
+            ctx: &mut ggez::Context,
             axis: ggez::event::Axis,
             value: i16,
             instance_id: i32,
         ) {
-            self.$handler.controller_axis_event(&mut self.$state, axis, value, instance_id);
+            $crate::log_dispatch_error(self.$handler.controller_axis_event_with_context(
+                ctx,
+                &mut self.$state,
+                axis,
+                value,
+                instance_id,
+            ));
         }
     };
 }
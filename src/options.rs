@@ -0,0 +1,296 @@
+use ggez::event::Mod;
+use ggez::nalgebra;
+use input_handler::PhysicalInputValue;
+use interaction::Interaction;
+
+/// Modifier keys requirement of a key binding.
+#[derive(PartialEq, Eq, Copy, Clone, Debug)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
+pub enum ModifierMatch {
+    /// Modifier keys are not looked at.
+    Any,
+    /// Held modifier keys have to be exactly these.
+    Exact(#[cfg_attr(feature = "serde", serde(with = "::profile::modifiers"))] Mod),
+    /// These modifier keys have to be held, others may be held as well.
+    Subset(#[cfg_attr(feature = "serde", serde(with = "::profile::modifiers"))] Mod),
+}
+
+impl ModifierMatch {
+    /// Checks held modifier keys against the requirement; lock keys (num, caps, mode) are never
+    /// taken into account.
+    pub fn matches(&self, held: Mod, distinguish_sides: bool) -> bool {
+        let held = normalize_modifiers(held, distinguish_sides);
+        match *self {
+            ModifierMatch::Any => true,
+            ModifierMatch::Exact(required) => {
+                held == normalize_modifiers(required, distinguish_sides)
+            }
+            ModifierMatch::Subset(required) => {
+                held.contains(normalize_modifiers(required, distinguish_sides))
+            }
+        }
+    }
+}
+
+/// How a key binding treats key repeats.
+#[derive(PartialEq, Eq, Copy, Clone, Debug)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
+pub enum RepeatPolicy {
+    /// Only the initial press is dispatched.
+    Ignore,
+    /// Only repeats are dispatched.
+    Only,
+    /// Both the initial press and repeats are dispatched.
+    Both,
+}
+
+impl RepeatPolicy {
+    /// Checks if a key event with given value should be dispatched; releases always are.
+    pub fn accepts(&self, value: PhysicalInputValue) -> bool {
+        match value {
+            PhysicalInputValue::Button(true) => *self != RepeatPolicy::Only,
+            PhysicalInputValue::Repeat => *self != RepeatPolicy::Ignore,
+            _ => true,
+        }
+    }
+}
+
+/// How a mouse wheel binding receives scrolling.
+#[derive(PartialEq, Eq, Copy, Clone, Debug)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
+pub enum WheelMode {
+    /// A press and a release per unit scrolled, for button-style consumers.
+    Buttons,
+    /// A single `PhysicalInputValue::Wheel` with the signed amount scrolled.
+    Delta,
+}
+
+/// How raw controller axis values map to -1.0..1.0 or, if unipolar, to 0.0..1.0; values
+/// outside the calibrated range are clamped.
+#[derive(PartialEq, Eq, Copy, Clone, Debug)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
+pub struct AxisNormalization {
+    /// Raw value at full negative deflection, or at rest if unipolar.
+    pub min: i16,
+    /// Raw value at rest; not used if unipolar.
+    pub center: i16,
+    /// Raw value at full positive deflection.
+    pub max: i16,
+    /// Whether the axis only goes one way, like a trigger does.
+    pub unipolar: bool,
+    /// Whether output is flipped: negated if bipolar, subtracted from 1.0 if unipolar.
+    pub inverted: bool,
+}
+
+impl AxisNormalization {
+    /// A stick axis, -32768..32767 around 0.
+    pub fn bipolar() -> Self {
+        AxisNormalization {
+            min: i16::min_value(),
+            center: 0,
+            max: i16::max_value(),
+            unipolar: false,
+            inverted: false,
+        }
+    }
+
+    /// A trigger axis, 0..32767.
+    pub fn trigger() -> Self {
+        AxisNormalization {
+            min: 0,
+            center: 0,
+            max: i16::max_value(),
+            unipolar: true,
+            inverted: false,
+        }
+    }
+
+    /// A stick axis with measured extremes and rest position.
+    pub fn calibrated(min: i16, center: i16, max: i16) -> Self {
+        AxisNormalization {
+            min,
+            center,
+            max,
+            unipolar: false,
+            inverted: false,
+        }
+    }
+
+    pub fn inverted(mut self, inverted: bool) -> Self {
+        self.inverted = inverted;
+        self
+    }
+
+    pub fn normalize(&self, raw_axis: i16) -> f32 {
+        let fraction = |from: i16, to: i16| {
+            if to == from {
+                0.0
+            } else {
+                let range = to as f32 - from as f32;
+                nalgebra::clamp((raw_axis as f32 - from as f32) / range, 0.0, 1.0)
+            }
+        };
+        if self.unipolar {
+            let value = fraction(self.min, self.max);
+            if self.inverted {
+                1.0 - value
+            } else {
+                value
+            }
+        } else {
+            let value = if raw_axis >= self.center {
+                fraction(self.center, self.max)
+            } else {
+                -fraction(self.center, self.min)
+            };
+            if self.inverted {
+                -value
+            } else {
+                value
+            }
+        }
+    }
+
+    /// Normalizes and scales back to the range `PhysicalInputValue::Axis` values are read in.
+    pub fn apply(&self, raw_axis: i16) -> i16 {
+        (self.normalize(raw_axis) * i16::max_value() as f32).round() as i16
+    }
+}
+
+impl Default for AxisNormalization {
+    fn default() -> Self {
+        AxisNormalization::bipolar()
+    }
+}
+
+/// Normalized deflections at which a half of a controller axis is pressed and released, as
+/// `PhysicalInput::CAxisButton`; keeping `release` below `press` stops noise from chattering.
+#[derive(PartialEq, Copy, Clone, Debug)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
+#[cfg_attr(feature = "serde", serde(default))]
+pub struct AxisThreshold {
+    pub press: f32,
+    pub release: f32,
+    /// How raw values are normalized before comparing; bipolar by default, independent of
+    /// normalization set in options of `PhysicalInput::CAxis` bindings.
+    pub normalization: AxisNormalization,
+}
+
+impl AxisThreshold {
+    pub fn new(press: f32, release: f32) -> Self {
+        AxisThreshold {
+            press,
+            release,
+            normalization: AxisNormalization::bipolar(),
+        }
+    }
+
+    pub fn normalization(mut self, normalization: AxisNormalization) -> Self {
+        self.normalization = normalization;
+        self
+    }
+}
+
+impl Default for AxisThreshold {
+    fn default() -> Self {
+        AxisThreshold::new(0.6, 0.4)
+    }
+}
+
+/// Options of a single physical-logical binding.
+#[derive(PartialEq, Clone, Debug)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
+#[cfg_attr(feature = "serde", serde(default))]
+pub struct BindingOptions {
+    /// Modifier keys requirement; only applies to `PhysicalInput::Key` bindings.
+    pub modifiers: ModifierMatch,
+    /// Whether left and right variants of modifier keys are told apart.
+    pub distinguish_sides: bool,
+    /// Key repeat handling; only applies to `PhysicalInput::Key` bindings.
+    pub repeat: RepeatPolicy,
+    /// When the logical input is pressed and released, relative to the physical input.
+    pub interaction: Interaction,
+    /// Renormalization of raw values; only applies to `PhysicalInput::CAxis` bindings.
+    pub normalization: Option<AxisNormalization>,
+    /// Scrolling delivery; only applies to `PhysicalInput::MWheelX` and `MWheelY` bindings.
+    pub wheel: WheelMode,
+    /// Dispatch order among bindings of the same physical input; higher goes first.
+    pub priority: i32,
+}
+
+impl BindingOptions {
+    pub fn new() -> Self {
+        BindingOptions {
+            modifiers: ModifierMatch::Any,
+            distinguish_sides: false,
+            repeat: RepeatPolicy::Ignore,
+            interaction: Interaction::Press,
+            normalization: None,
+            wheel: WheelMode::Buttons,
+            priority: 0,
+        }
+    }
+
+    pub fn modifiers(mut self, modifiers: ModifierMatch) -> Self {
+        self.modifiers = modifiers;
+        self
+    }
+
+    pub fn distinguish_sides(mut self, distinguish_sides: bool) -> Self {
+        self.distinguish_sides = distinguish_sides;
+        self
+    }
+
+    pub fn repeat(mut self, repeat: RepeatPolicy) -> Self {
+        self.repeat = repeat;
+        self
+    }
+
+    pub fn interaction(mut self, interaction: Interaction) -> Self {
+        self.interaction = interaction;
+        self
+    }
+
+    pub fn normalization(mut self, normalization: AxisNormalization) -> Self {
+        self.normalization = Some(normalization);
+        self
+    }
+
+    pub fn wheel(mut self, wheel: WheelMode) -> Self {
+        self.wheel = wheel;
+        self
+    }
+
+    pub fn priority(mut self, priority: i32) -> Self {
+        self.priority = priority;
+        self
+    }
+}
+
+impl Default for BindingOptions {
+    fn default() -> Self {
+        BindingOptions::new()
+    }
+}
+
+/// Strips lock keys and, unless distinguishing sides, makes either side of a modifier mean both.
+fn normalize_modifiers(modifiers: Mod, distinguish_sides: bool) -> Mod {
+    let pairs = [
+        (Mod::LSHIFTMOD, Mod::RSHIFTMOD),
+        (Mod::LCTRLMOD, Mod::RCTRLMOD),
+        (Mod::LALTMOD, Mod::RALTMOD),
+        (Mod::LGUIMOD, Mod::RGUIMOD),
+    ];
+    let mut normalized = Mod::empty();
+    for &(left, right) in &pairs {
+        let held = modifiers & (left | right);
+        if !held.is_empty() {
+            normalized |= if distinguish_sides {
+                held
+            } else {
+                left | right
+            };
+        }
+    }
+    normalized
+}
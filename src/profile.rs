@@ -0,0 +1,426 @@
+//! Serializable binding profiles, available with the `serde` cargo feature.
+//!
+//! A `BindingProfile` is a flat list of physical-logical pairs, and is format-agnostic: anything
+//! `serde` can read or write will do. Physical inputs use `serde`'s default externally tagged
+//! enum representation, with SDL2 types written as their SDL2 names:
+//!
+//! * `Keycode` - key name as returned by SDL2, e.g. `"Escape"`, `"Left Shift"`, `"A"`;
+//! * `MouseButton` - one of `"Left"`, `"Middle"`, `"Right"`, `"X1"`, `"X2"`, `"Unknown"`;
+//! * controller `Button` - SDL2 game controller name, e.g. `"a"`, `"back"`, `"dpleft"`;
+//! * controller `Axis` - SDL2 game controller name, e.g. `"leftx"`, `"righttrigger"`.
+//!
+//! In TOML, with `Input` being a plain enum:
+//!
+//! ```toml
+//! [[bindings]]
+//! physical = { Key = "Escape" }
+//! logical = "Exit"
+//!
+//! [[bindings]]
+//! physical = { CAxis = [0, "leftx"] }
+//! logical = "Spin"
+//!
+//! [[bindings]]
+//! physical = { CAxisButton = [0, "righttrigger", true] }
+//! logical = "Fire"
+//!
+//! [[bindings]]
+//! physical = { MWheelY = true }
+//! logical = "ZoomIn"
+//!
+//! [[bindings]]
+//! physical = "MMotion"
+//! logical = "Look"
+//! ```
+//!
+//! The same in JSON is `{"bindings":[{"physical":{"Key":"Escape"},"logical":"Exit"},...]}`,
+//! and in RON `(bindings:[(physical:Key("Escape"),logical:Exit),...])`.
+//!
+//! Each binding may also have `options`, a `BindingOptions`; missing fields take their default
+//! values. Modifier keys in it are written as lists of `"LShift"`, `"RShift"`, `"LCtrl"`,
+//! `"RCtrl"`, `"LAlt"`, `"RAlt"`, `"LGui"`, `"RGui"`, `"Num"`, `"Caps"` and `"Mode"`:
+//!
+//! ```toml
+//! [[bindings]]
+//! physical = { Key = "S" }
+//! logical = "Save"
+//! options = { modifiers = { Exact = ["LCtrl"] } }
+//! ```
+//!
+//! Bindings of named binding contexts go in `contexts`, each with its `name`, `fallthrough`
+//! (`false` if missing) and `bindings` written the same way as above:
+//!
+//! ```toml
+//! [[contexts]]
+//! name = "vehicle"
+//! fallthrough = true
+//!
+//! [[contexts.bindings]]
+//! physical = { Key = "W" }
+//! logical = "Accelerate"
+//! ```
+//!
+//! Thresholds of `PhysicalInput::CAxisButton` halves go in `axis_thresholds`, each with its
+//! `axis` and `threshold`, an `AxisThreshold`; missing fields take their default values:
+//!
+//! ```toml
+//! [[axis_thresholds]]
+//! axis = "righttrigger"
+//!
+//! [axis_thresholds.threshold]
+//! press = 0.6
+//! release = 0.4
+//! normalization = { min = 0, center = 0, max = 32767, unipolar = true, inverted = false }
+//! ```
+//!
+//! Unknown names are reported as deserialization errors, e.g. "unknown key name `Foo`".
+
+use ggez::event::{Axis, Button, Keycode, Mod, MouseButton};
+use input_handler::{mouse_button_from_name, mouse_button_name, InputHandler, PhysicalInput};
+use options::{AxisThreshold, BindingOptions};
+use std::fmt::Debug;
+use std::hash::Hash;
+
+/// A single physical-logical binding pair.
+#[derive(PartialEq, Clone, Debug, Serialize, Deserialize)]
+pub struct Binding<LogicalInput> {
+    pub physical: PhysicalInput,
+    pub logical: LogicalInput,
+    #[serde(default)]
+    pub options: BindingOptions,
+}
+
+/// Bindings of a named binding context.
+#[derive(PartialEq, Clone, Debug, Serialize, Deserialize)]
+pub struct ContextProfile<LogicalInput> {
+    pub name: String,
+    #[serde(default)]
+    pub fallthrough: bool,
+    pub bindings: Vec<Binding<LogicalInput>>,
+}
+
+/// Threshold of a controller axis' halves, as set with `InputHandler::set_axis_threshold()`.
+#[derive(PartialEq, Copy, Clone, Debug, Serialize, Deserialize)]
+pub struct AxisThresholdProfile {
+    #[serde(with = "axis")]
+    pub axis: Axis,
+    pub threshold: AxisThreshold,
+}
+
+/// A set of bindings that can be exported from and applied to an `InputHandler`.
+#[derive(PartialEq, Clone, Debug, Serialize, Deserialize)]
+pub struct BindingProfile<LogicalInput> {
+    /// Bindings of the base context.
+    pub bindings: Vec<Binding<LogicalInput>>,
+    #[serde(default)]
+    pub contexts: Vec<ContextProfile<LogicalInput>>,
+    #[serde(default)]
+    pub axis_thresholds: Vec<AxisThresholdProfile>,
+}
+
+impl<LogicalInput> BindingProfile<LogicalInput>
+where
+    LogicalInput: Hash + Eq + Clone + Debug,
+{
+    pub fn new() -> Self {
+        BindingProfile {
+            bindings: Vec::new(),
+            contexts: Vec::new(),
+            axis_thresholds: Vec::new(),
+        }
+    }
+
+    /// Captures every binding of given handler, in the base and named contexts, and its axis
+    /// thresholds.
+    pub fn from_handler<State>(handler: &InputHandler<LogicalInput, State>) -> Self {
+        let mut names = handler.context_names().collect::<Vec<_>>();
+        names.sort();
+        let mut axis_thresholds = handler
+            .axis_thresholds()
+            .map(|(axis, threshold)| AxisThresholdProfile {
+                axis,
+                threshold: *threshold,
+            })
+            .collect::<Vec<_>>();
+        axis_thresholds.sort_by_key(|axis_threshold| axis_threshold.axis.string());
+        BindingProfile {
+            bindings: bindings_of(handler.bindings()),
+            contexts: names
+                .into_iter()
+                .filter_map(|name| {
+                    handler.context(name).map(|context| ContextProfile {
+                        name: name.to_owned(),
+                        fallthrough: context.fallthrough(),
+                        bindings: bindings_of(context.bindings()),
+                    })
+                })
+                .collect(),
+            axis_thresholds,
+        }
+    }
+
+    /// Replaces every binding of given handler's base context with the ones in this profile,
+    /// and redefines named contexts and sets axis thresholds it has; other named contexts and
+    /// axis thresholds are left as they are.
+    pub fn apply<State>(&self, handler: &mut InputHandler<LogicalInput, State>) {
+        handler.clear_bindings();
+        for binding in &self.bindings {
+            handler.bind_with(
+                binding.physical,
+                binding.logical.clone(),
+                binding.options.clone(),
+            );
+        }
+        for context_profile in &self.contexts {
+            let context =
+                handler.define_context(&context_profile.name, context_profile.fallthrough);
+            for binding in &context_profile.bindings {
+                context.bind_with(
+                    binding.physical,
+                    binding.logical.clone(),
+                    binding.options.clone(),
+                );
+            }
+        }
+        for axis_threshold in &self.axis_thresholds {
+            handler.set_axis_threshold(axis_threshold.axis, axis_threshold.threshold);
+        }
+    }
+}
+
+fn bindings_of<'a, LogicalInput, I>(bindings: I) -> Vec<Binding<LogicalInput>>
+where
+    LogicalInput: 'a + Clone,
+    I: Iterator<Item = (PhysicalInput, &'a LogicalInput, &'a BindingOptions)>,
+{
+    bindings
+        .map(|(physical, logical, options)| Binding {
+            physical,
+            logical: logical.clone(),
+            options: options.clone(),
+        })
+        .collect()
+}
+
+pub(crate) mod keycode {
+    use super::*;
+    use serde::de::Error;
+    use serde::{Deserialize, Deserializer, Serializer};
+
+    pub fn serialize<S: Serializer>(keycode: &Keycode, serializer: S) -> Result<S::Ok, S::Error> {
+        serializer.serialize_str(&keycode.name())
+    }
+
+    pub fn deserialize<'de, D: Deserializer<'de>>(deserializer: D) -> Result<Keycode, D::Error> {
+        let name = String::deserialize(deserializer)?;
+        Keycode::from_name(&name)
+            .ok_or_else(|| D::Error::custom(format!("unknown key name `{}`", name)))
+    }
+}
+
+pub(crate) mod mouse_button {
+    use super::*;
+    use serde::de::Error;
+    use serde::{Deserialize, Deserializer, Serializer};
+
+    pub fn serialize<S: Serializer>(
+        button: &MouseButton,
+        serializer: S,
+    ) -> Result<S::Ok, S::Error> {
+        serializer.serialize_str(mouse_button_name(*button))
+    }
+
+    pub fn deserialize<'de, D: Deserializer<'de>>(
+        deserializer: D,
+    ) -> Result<MouseButton, D::Error> {
+        let name = String::deserialize(deserializer)?;
+        mouse_button_from_name(&name)
+            .ok_or_else(|| D::Error::custom(format!("unknown mouse button name `{}`", name)))
+    }
+}
+
+pub(crate) mod button {
+    use super::*;
+    use serde::de::Error;
+    use serde::{Deserialize, Deserializer, Serializer};
+
+    pub fn serialize<S: Serializer>(button: &Button, serializer: S) -> Result<S::Ok, S::Error> {
+        serializer.serialize_str(&button.string())
+    }
+
+    pub fn deserialize<'de, D: Deserializer<'de>>(deserializer: D) -> Result<Button, D::Error> {
+        let name = String::deserialize(deserializer)?;
+        Button::from_string(&name)
+            .ok_or_else(|| D::Error::custom(format!("unknown controller button name `{}`", name)))
+    }
+}
+
+pub(crate) mod axis {
+    use super::*;
+    use serde::de::Error;
+    use serde::{Deserialize, Deserializer, Serializer};
+
+    pub fn serialize<S: Serializer>(axis: &Axis, serializer: S) -> Result<S::Ok, S::Error> {
+        serializer.serialize_str(&axis.string())
+    }
+
+    pub fn deserialize<'de, D: Deserializer<'de>>(deserializer: D) -> Result<Axis, D::Error> {
+        let name = String::deserialize(deserializer)?;
+        Axis::from_string(&name)
+            .ok_or_else(|| D::Error::custom(format!("unknown controller axis name `{}`", name)))
+    }
+}
+
+pub(crate) mod modifiers {
+    use super::*;
+    use serde::de::Error;
+    use serde::{Deserialize, Deserializer, Serializer};
+
+    const NAMES: [(Mod, &str); 11] = [
+        (Mod::LSHIFTMOD, "LShift"),
+        (Mod::RSHIFTMOD, "RShift"),
+        (Mod::LCTRLMOD, "LCtrl"),
+        (Mod::RCTRLMOD, "RCtrl"),
+        (Mod::LALTMOD, "LAlt"),
+        (Mod::RALTMOD, "RAlt"),
+        (Mod::LGUIMOD, "LGui"),
+        (Mod::RGUIMOD, "RGui"),
+        (Mod::NUMMOD, "Num"),
+        (Mod::CAPSMOD, "Caps"),
+        (Mod::MODEMOD, "Mode"),
+    ];
+
+    pub fn serialize<S: Serializer>(modifiers: &Mod, serializer: S) -> Result<S::Ok, S::Error> {
+        serializer.collect_seq(
+            NAMES
+                .iter()
+                .filter(|&&(modifier, _)| modifiers.contains(modifier))
+                .map(|&(_, name)| name),
+        )
+    }
+
+    pub fn deserialize<'de, D: Deserializer<'de>>(deserializer: D) -> Result<Mod, D::Error> {
+        let mut modifiers = Mod::empty();
+        for name in Vec::<String>::deserialize(deserializer)? {
+            match NAMES.iter().find(|&&(_, known)| known == name) {
+                Some(&(modifier, _)) => modifiers |= modifier,
+                None => {
+                    return Err(D::Error::custom(format!(
+                        "unknown modifier key name `{}`",
+                        name
+                    )))
+                }
+            }
+        }
+        Ok(modifiers)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serde_json;
+
+    #[test]
+    fn round_trip() {
+        let physicals = vec![
+            PhysicalInput::CAxis(1, Axis::TriggerRight),
+            PhysicalInput::CAxisButton(0, Axis::LeftY, false),
+            PhysicalInput::CButton(0, Button::DPadLeft),
+            PhysicalInput::MButton(MouseButton::X2),
+            PhysicalInput::MWheelX(true),
+            PhysicalInput::MWheelY(false),
+            PhysicalInput::MMotion,
+            PhysicalInput::Key(Keycode::Escape),
+        ];
+        let mut handler = InputHandler::<usize, ()>::new();
+        for (logical, physical) in physicals.iter().enumerate() {
+            handler.bind(*physical, logical);
+        }
+
+        let json = serde_json::to_string(&BindingProfile::from_handler(&handler)).unwrap();
+        let profile: BindingProfile<usize> = serde_json::from_str(&json).unwrap();
+        assert_eq!(
+            profile
+                .bindings
+                .iter()
+                .map(|binding| binding.physical)
+                .collect::<Vec<_>>(),
+            physicals
+        );
+        let mut restored = InputHandler::<usize, ()>::new();
+        profile.apply(&mut restored);
+        for (logical, physical) in physicals.iter().enumerate() {
+            assert_eq!(
+                restored.bindings_for(&logical).collect::<Vec<_>>(),
+                vec![*physical]
+            );
+        }
+        assert_eq!(BindingProfile::from_handler(&restored), profile);
+    }
+
+    #[test]
+    fn contexts_round_trip() {
+        let mut handler = InputHandler::<usize, ()>::new();
+        handler.bind(PhysicalInput::Key(Keycode::Escape), 0);
+        handler
+            .define_context("vehicle", true)
+            .bind(PhysicalInput::Key(Keycode::W), 1);
+        handler
+            .define_context("menu", false)
+            .bind(PhysicalInput::Key(Keycode::Return), 2);
+
+        let json = serde_json::to_string(&BindingProfile::from_handler(&handler)).unwrap();
+        let profile: BindingProfile<usize> = serde_json::from_str(&json).unwrap();
+        assert_eq!(
+            profile
+                .contexts
+                .iter()
+                .map(|context| context.name.as_str())
+                .collect::<Vec<_>>(),
+            vec!["menu", "vehicle"]
+        );
+        let mut restored = InputHandler::<usize, ()>::new();
+        profile.apply(&mut restored);
+        assert_eq!(BindingProfile::from_handler(&restored), profile);
+        assert!(restored.context("vehicle").unwrap().fallthrough());
+
+        let json = r#"{"bindings":[{"physical":{"Key":"Escape"},"logical":0}]}"#;
+        let profile: BindingProfile<usize> = serde_json::from_str(json).unwrap();
+        assert!(profile.contexts.is_empty());
+    }
+
+    #[test]
+    fn axis_thresholds_round_trip() {
+        use options::AxisNormalization;
+
+        let mut handler = InputHandler::<usize, ()>::new();
+        handler
+            .set_axis_threshold(
+                Axis::TriggerRight,
+                AxisThreshold::new(0.6, 0.4).normalization(AxisNormalization::trigger()),
+            )
+            .set_axis_threshold(Axis::LeftX, AxisThreshold::new(0.5, 0.25));
+
+        let json = serde_json::to_string(&BindingProfile::from_handler(&handler)).unwrap();
+        let profile: BindingProfile<usize> = serde_json::from_str(&json).unwrap();
+        let mut restored = InputHandler::<usize, ()>::new();
+        profile.apply(&mut restored);
+        assert_eq!(BindingProfile::from_handler(&restored), profile);
+        assert_eq!(profile.axis_thresholds.len(), 2);
+
+        let json = r#"{"bindings":[],"axis_thresholds":[{"axis":"leftx","threshold":{}}]}"#;
+        let profile: BindingProfile<usize> = serde_json::from_str(json).unwrap();
+        assert_eq!(
+            profile.axis_thresholds[0].threshold,
+            AxisThreshold::default()
+        );
+    }
+
+    #[test]
+    fn unknown_key_name() {
+        let json = r#"{"bindings":[{"physical":{"Key":"NotAKey"},"logical":0}]}"#;
+        let error = serde_json::from_str::<BindingProfile<usize>>(json).unwrap_err();
+        assert!(error.to_string().contains("unknown key name `NotAKey`"));
+    }
+}
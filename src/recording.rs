@@ -0,0 +1,622 @@
+//! Recordings of physical input events, for reproducing what happened in a play session.
+//!
+//! `InputHandler::start_recording()` makes the handler note every physical input event it
+//! dispatches, as a `RecordedEvent`, until `InputHandler::stop_recording()` hands them over as a
+//! `Recording`. Events derived by the handler itself, like `PhysicalInput::CAxisButton` presses,
+//! are recorded as well, so that feeding a recording to `InputHandler::replay_event()` in order
+//! reproduces the original dispatch, as `replay::Replay` does. Each event has:
+//!
+//! * frame - number of `InputHandler::update()` calls since the recording started;
+//! * timestamp - seconds of `InputHandler` time since the recording started; it's monotonic as
+//!   long as time isn't set back with `InputHandler::set_time()`;
+//! * physical input and its value;
+//! * modifier keys held, as of the last key event.
+//!
+//! Recordings also note when each frame ended, so that time-driven interactions and sequences
+//! that complete within a frame without any events replay at the same time.
+//!
+//! Recordings can be written in two formats, both versioned by `FORMAT_VERSION`; SDL2 types are
+//! written as their SDL2 names, as in binding profiles.
+//!
+//! The text format is line-oriented. The first line is `inputty-recording <version>`; blank
+//! lines and lines starting with `#` are skipped. Every other line is either an event, as five
+//! tab-separated fields: frame, timestamp, physical input, value, and modifier keys as
+//! hexadecimal SDL2 bits; or the end of a frame, as two: frame and timestamp. Physical inputs
+//! and values are a name followed by space-separated arguments. With tabs shown as spaces:
+//!
+//! ```text
+//! inputty-recording 2
+//! # frame    timestamp    physical    value    keymod
+//! 0    0    Key Left Shift    Button down    0x0001
+//! 0    0.016666668
+//! 1    0.033333335
+//! 2    0.05
+//! 3    0.05    CAxis 0 leftx    Axis -32768    0x0001
+//! 3    0.05    CAxisButton 0 leftx -    Button down    0x0001
+//! 3    0.06666667
+//! ```
+//!
+//! Physical inputs are `CAxis <instance> <axis>`, `CAxisButton <instance> <axis> <+|->`,
+//! `CButton <instance> <button>`, `MButton <mouse button>`, `MWheelX <+|->`, `MWheelY <+|->`,
+//! `MMotion` and `Key <key name>`; key names may contain spaces. Values are `Axis <raw>`,
+//! `Button <down|up>`, `Repeat`, `XY <x> <y> <relative x> <relative y>` and `Wheel <delta>`.
+//!
+//! The binary format starts with the 4 bytes `INPT` and the version as `u16`, followed by the
+//! number of frames as `u32` and the timestamp each one ended at as `f32`; the rest are events,
+//! until the end of the stream. All numbers are little-endian. An event is its frame as
+//! `u32`, timestamp as `f32`, modifier keys as `u16`, physical input and value. Each of the last
+//! two is a tag byte followed by its fields, in the same order as in the text format; booleans
+//! are a byte of 0 or 1, and names are a byte of length followed by as many bytes of UTF-8.
+//!
+//! | Tag | Physical input | Value |
+//! |-----|----------------|-------|
+//! | 0 | `CAxis`: `i32`, name | `Axis`: `i16` |
+//! | 1 | `CAxisButton`: `i32`, name, boolean | `Button`: boolean |
+//! | 2 | `CButton`: `i32`, name | `Repeat` |
+//! | 3 | `MButton`: name | `XY`: 4 `i32` |
+//! | 4 | `MWheelX`: boolean | `Wheel`: `i32` |
+//! | 5 | `MWheelY`: boolean | |
+//! | 6 | `MMotion` | |
+//! | 7 | `Key`: name | |
+//!
+//! Version 1 recordings are the same, except that they don't note the ends of frames.
+
+use error::InputtyError;
+use ggez::event::{Axis, Button, Keycode, Mod};
+use input_handler::{mouse_button_from_name, mouse_button_name, PhysicalInput, PhysicalInputValue};
+use std::io::{BufRead, Read, Write};
+
+/// Version of both recording formats written by this crate; older versions are still read.
+pub const FORMAT_VERSION: u16 = 2;
+
+const TEXT_HEADER: &str = "inputty-recording";
+const BINARY_MAGIC: &[u8; 4] = b"INPT";
+
+/// A single physical input event, as dispatched by `InputHandler`.
+#[derive(PartialEq, Copy, Clone, Debug)]
+pub struct RecordedEvent {
+    pub frame: u32,
+    pub timestamp: f32,
+    pub physical: PhysicalInput,
+    pub value: PhysicalInputValue,
+    pub keymod: Mod,
+}
+
+/// Physical input events in the order they were dispatched in.
+#[derive(PartialEq, Clone, Debug, Default)]
+pub struct Recording {
+    pub events: Vec<RecordedEvent>,
+    /// Timestamps at which frames ended with `InputHandler::update()`, by frame.
+    pub frame_ends: Vec<f32>,
+}
+
+impl Recording {
+    pub fn new() -> Self {
+        Recording {
+            events: Vec::new(),
+            frame_ends: Vec::new(),
+        }
+    }
+
+    pub fn write_text<W: Write>(&self, mut writer: W) -> Result<(), InputtyError> {
+        writeln!(writer, "{} {}", TEXT_HEADER, FORMAT_VERSION).map_err(io_error)?;
+        writeln!(writer, "# frame\ttimestamp\tphysical\tvalue\tkeymod").map_err(io_error)?;
+        // Ends of frames go after the frame's events.
+        let mut frame = 0;
+        for event in &self.events {
+            while frame < self.frame_ends.len() && (frame as u32) < event.frame {
+                writeln!(writer, "{}\t{}", frame, self.frame_ends[frame]).map_err(io_error)?;
+                frame += 1;
+            }
+            writeln!(
+                writer,
+                "{}\t{}\t{}\t{}\t{:#06x}",
+                event.frame,
+                event.timestamp,
+                physical_to_text(&event.physical),
+                value_to_text(&event.value),
+                event.keymod.bits()
+            )
+            .map_err(io_error)?;
+        }
+        for (frame, timestamp) in self.frame_ends.iter().enumerate().skip(frame) {
+            writeln!(writer, "{}\t{}", frame, timestamp).map_err(io_error)?;
+        }
+        Ok(())
+    }
+
+    pub fn write_binary<W: Write>(&self, mut writer: W) -> Result<(), InputtyError> {
+        let mut bytes = BINARY_MAGIC.to_vec();
+        put_u16(&mut bytes, FORMAT_VERSION);
+        put_u32(&mut bytes, self.frame_ends.len() as u32);
+        for timestamp in &self.frame_ends {
+            put_u32(&mut bytes, timestamp.to_bits());
+        }
+        for event in &self.events {
+            put_u32(&mut bytes, event.frame);
+            put_u32(&mut bytes, event.timestamp.to_bits());
+            put_u16(&mut bytes, event.keymod.bits());
+            put_physical(&mut bytes, &event.physical);
+            put_value(&mut bytes, &event.value);
+        }
+        writer.write_all(&bytes).map_err(io_error)
+    }
+
+    /// Reads a recording in either format, telling them apart by the header.
+    pub fn read<R: Read>(mut reader: R) -> Result<Recording, InputtyError> {
+        let mut bytes = Vec::new();
+        reader.read_to_end(&mut bytes).map_err(io_error)?;
+        if bytes.starts_with(BINARY_MAGIC) {
+            read_binary(&bytes[BINARY_MAGIC.len()..])
+        } else {
+            read_text(&bytes[..])
+        }
+    }
+}
+
+/// Notes events dispatched by `InputHandler` while recording.
+pub(crate) struct Recorder {
+    recording: Recording,
+    start_time: f32,
+    frame: u32,
+}
+
+impl Recorder {
+    pub fn new(start_time: f32) -> Self {
+        Recorder {
+            recording: Recording::new(),
+            start_time,
+            frame: 0,
+        }
+    }
+
+    pub fn end_frame(&mut self, time: f32) {
+        self.recording.frame_ends.push(time - self.start_time);
+        self.frame += 1;
+    }
+
+    pub fn record(
+        &mut self,
+        time: f32,
+        physical: PhysicalInput,
+        value: PhysicalInputValue,
+        keymod: Mod,
+    ) {
+        self.recording.events.push(RecordedEvent {
+            frame: self.frame,
+            timestamp: time - self.start_time,
+            physical,
+            value,
+            keymod,
+        });
+    }
+
+    pub fn finish(self) -> Recording {
+        self.recording
+    }
+}
+
+fn io_error(error: ::std::io::Error) -> InputtyError {
+    InputtyError::with_source("Couldn't access recording", error)
+}
+
+fn parse_error(line: usize, message: &str) -> InputtyError {
+    InputtyError::new(format!("Malformed recording at line {}: {}", line, message))
+}
+
+fn field<T>(value: Option<T>, line: usize, what: &str) -> Result<T, InputtyError> {
+    value.ok_or_else(|| parse_error(line, &format!("invalid {}", what)))
+}
+
+fn sign(positive: bool) -> &'static str {
+    if positive {
+        "+"
+    } else {
+        "-"
+    }
+}
+
+fn physical_to_text(physical: &PhysicalInput) -> String {
+    match *physical {
+        PhysicalInput::CAxis(id, axis) => format!("CAxis {} {}", id, axis.string()),
+        PhysicalInput::CAxisButton(id, axis, positive) => {
+            format!("CAxisButton {} {} {}", id, axis.string(), sign(positive))
+        }
+        PhysicalInput::CButton(id, button) => format!("CButton {} {}", id, button.string()),
+        PhysicalInput::MButton(button) => format!("MButton {}", mouse_button_name(button)),
+        PhysicalInput::MWheelX(positive) => format!("MWheelX {}", sign(positive)),
+        PhysicalInput::MWheelY(positive) => format!("MWheelY {}", sign(positive)),
+        PhysicalInput::MMotion => "MMotion".to_owned(),
+        PhysicalInput::Key(keycode) => format!("Key {}", keycode.name()),
+    }
+}
+
+fn value_to_text(value: &PhysicalInputValue) -> String {
+    match *value {
+        PhysicalInputValue::Axis(raw_axis) => format!("Axis {}", raw_axis),
+        PhysicalInputValue::Button(true) => "Button down".to_owned(),
+        PhysicalInputValue::Button(false) => "Button up".to_owned(),
+        PhysicalInputValue::Repeat => "Repeat".to_owned(),
+        PhysicalInputValue::XY(x, y, xrel, yrel) => format!("XY {} {} {} {}", x, y, xrel, yrel),
+        PhysicalInputValue::Wheel(delta) => format!("Wheel {}", delta),
+    }
+}
+
+fn read_text(bytes: &[u8]) -> Result<Recording, InputtyError> {
+    let mut recording = Recording::new();
+    let mut lines = bytes.lines().enumerate();
+    let header = match lines.next() {
+        Some((_, line)) => line.map_err(io_error)?,
+        None => return Err(parse_error(1, "no header")),
+    };
+    match header.split_whitespace().collect::<Vec<_>>()[..] {
+        [TEXT_HEADER, version] => {
+            check_version(version.parse().ok())?;
+        }
+        _ => return Err(parse_error(1, "not a recording")),
+    }
+    for (index, line) in lines {
+        let line = line.map_err(io_error)?;
+        if line.trim().is_empty() || line.starts_with('#') {
+            continue;
+        }
+        let fields = line.split('\t').collect::<Vec<_>>();
+        let line = index + 1;
+        if fields.len() == 2 {
+            let frame: usize = field(fields[0].parse().ok(), line, "frame")?;
+            if frame != recording.frame_ends.len() {
+                return Err(parse_error(line, "frame ends out of order"));
+            }
+            let timestamp = field(fields[1].parse().ok(), line, "timestamp")?;
+            recording.frame_ends.push(timestamp);
+            continue;
+        }
+        if fields.len() != 5 {
+            return Err(parse_error(line, "expected 2 or 5 tab-separated fields"));
+        }
+        let keymod = if fields[4].starts_with("0x") {
+            u16::from_str_radix(&fields[4][2..], 16).ok()
+        } else {
+            None
+        };
+        recording.events.push(RecordedEvent {
+            frame: field(fields[0].parse().ok(), line, "frame")?,
+            timestamp: field(fields[1].parse().ok(), line, "timestamp")?,
+            physical: field(physical_from_text(fields[2]), line, "physical input")?,
+            value: field(value_from_text(fields[3]), line, "value")?,
+            keymod: field(keymod.map(Mod::from_bits_truncate), line, "modifier keys")?,
+        });
+    }
+    Ok(recording)
+}
+
+fn physical_from_text(text: &str) -> Option<PhysicalInput> {
+    let mut words = text.splitn(2, ' ');
+    let name = words.next()?;
+    let rest = words.next().unwrap_or("");
+    let args = rest.split(' ').collect::<Vec<_>>();
+    let positive = |word: &str| match word {
+        "+" => Some(true),
+        "-" => Some(false),
+        _ => None,
+    };
+    match (name, &args[..]) {
+        ("CAxis", &[id, axis]) => Some(PhysicalInput::CAxis(
+            id.parse().ok()?,
+            Axis::from_string(axis)?,
+        )),
+        ("CAxisButton", &[id, axis, half]) => Some(PhysicalInput::CAxisButton(
+            id.parse().ok()?,
+            Axis::from_string(axis)?,
+            positive(half)?,
+        )),
+        ("CButton", &[id, button]) => Some(PhysicalInput::CButton(
+            id.parse().ok()?,
+            Button::from_string(button)?,
+        )),
+        ("MButton", &[button]) => Some(PhysicalInput::MButton(mouse_button_from_name(button)?)),
+        ("MWheelX", &[half]) => Some(PhysicalInput::MWheelX(positive(half)?)),
+        ("MWheelY", &[half]) => Some(PhysicalInput::MWheelY(positive(half)?)),
+        ("MMotion", _) if rest.is_empty() => Some(PhysicalInput::MMotion),
+        ("Key", _) => Some(PhysicalInput::Key(Keycode::from_name(rest)?)),
+        _ => None,
+    }
+}
+
+fn value_from_text(text: &str) -> Option<PhysicalInputValue> {
+    let words = text.split(' ').collect::<Vec<_>>();
+    match words[..] {
+        ["Axis", raw_axis] => Some(PhysicalInputValue::Axis(raw_axis.parse().ok()?)),
+        ["Button", "down"] => Some(PhysicalInputValue::Button(true)),
+        ["Button", "up"] => Some(PhysicalInputValue::Button(false)),
+        ["Repeat"] => Some(PhysicalInputValue::Repeat),
+        ["XY", x, y, xrel, yrel] => Some(PhysicalInputValue::XY(
+            x.parse().ok()?,
+            y.parse().ok()?,
+            xrel.parse().ok()?,
+            yrel.parse().ok()?,
+        )),
+        ["Wheel", delta] => Some(PhysicalInputValue::Wheel(delta.parse().ok()?)),
+        _ => None,
+    }
+}
+
+fn check_version(version: Option<u16>) -> Result<u16, InputtyError> {
+    match version {
+        Some(version @ 1..=FORMAT_VERSION) => Ok(version),
+        Some(version) => Err(InputtyError::new(format!(
+            "Unsupported recording format version {}",
+            version
+        ))),
+        None => Err(InputtyError::new("Malformed recording format version")),
+    }
+}
+
+fn put_u16(bytes: &mut Vec<u8>, value: u16) {
+    bytes.extend_from_slice(&[value as u8, (value >> 8) as u8]);
+}
+
+fn put_u32(bytes: &mut Vec<u8>, value: u32) {
+    put_u16(bytes, value as u16);
+    put_u16(bytes, (value >> 16) as u16);
+}
+
+fn put_name(bytes: &mut Vec<u8>, name: &str) {
+    bytes.push(name.len() as u8);
+    bytes.extend_from_slice(name.as_bytes());
+}
+
+fn put_physical(bytes: &mut Vec<u8>, physical: &PhysicalInput) {
+    match *physical {
+        PhysicalInput::CAxis(id, axis) => {
+            bytes.push(0);
+            put_u32(bytes, id as u32);
+            put_name(bytes, &axis.string());
+        }
+        PhysicalInput::CAxisButton(id, axis, positive) => {
+            bytes.push(1);
+            put_u32(bytes, id as u32);
+            put_name(bytes, &axis.string());
+            bytes.push(positive as u8);
+        }
+        PhysicalInput::CButton(id, button) => {
+            bytes.push(2);
+            put_u32(bytes, id as u32);
+            put_name(bytes, &button.string());
+        }
+        PhysicalInput::MButton(button) => {
+            bytes.push(3);
+            put_name(bytes, mouse_button_name(button));
+        }
+        PhysicalInput::MWheelX(positive) => bytes.extend_from_slice(&[4, positive as u8]),
+        PhysicalInput::MWheelY(positive) => bytes.extend_from_slice(&[5, positive as u8]),
+        PhysicalInput::MMotion => bytes.push(6),
+        PhysicalInput::Key(keycode) => {
+            bytes.push(7);
+            put_name(bytes, &keycode.name());
+        }
+    }
+}
+
+fn put_value(bytes: &mut Vec<u8>, value: &PhysicalInputValue) {
+    match *value {
+        PhysicalInputValue::Axis(raw_axis) => {
+            bytes.push(0);
+            put_u16(bytes, raw_axis as u16);
+        }
+        PhysicalInputValue::Button(raw_button) => bytes.extend_from_slice(&[1, raw_button as u8]),
+        PhysicalInputValue::Repeat => bytes.push(2),
+        PhysicalInputValue::XY(x, y, xrel, yrel) => {
+            bytes.push(3);
+            for &coordinate in &[x, y, xrel, yrel] {
+                put_u32(bytes, coordinate as u32);
+            }
+        }
+        PhysicalInputValue::Wheel(delta) => {
+            bytes.push(4);
+            put_u32(bytes, delta as u32);
+        }
+    }
+}
+
+/// Cursor over the binary format; every read fails with `None` past the end.
+struct BinaryReader<'a> {
+    bytes: &'a [u8],
+}
+
+impl<'a> BinaryReader<'a> {
+    fn take(&mut self, count: usize) -> Option<&'a [u8]> {
+        if self.bytes.len() < count {
+            return None;
+        }
+        let (taken, rest) = self.bytes.split_at(count);
+        self.bytes = rest;
+        Some(taken)
+    }
+
+    fn u8(&mut self) -> Option<u8> {
+        self.take(1).map(|bytes| bytes[0])
+    }
+
+    fn bool(&mut self) -> Option<bool> {
+        match self.u8()? {
+            0 => Some(false),
+            1 => Some(true),
+            _ => None,
+        }
+    }
+
+    fn u16(&mut self) -> Option<u16> {
+        self.take(2)
+            .map(|bytes| bytes[0] as u16 | (bytes[1] as u16) << 8)
+    }
+
+    fn u32(&mut self) -> Option<u32> {
+        Some(self.u16()? as u32 | (self.u16()? as u32) << 16)
+    }
+
+    fn name(&mut self) -> Option<&'a str> {
+        let length = self.u8()? as usize;
+        ::std::str::from_utf8(self.take(length)?).ok()
+    }
+
+    fn physical(&mut self) -> Option<PhysicalInput> {
+        match self.u8()? {
+            0 => Some(PhysicalInput::CAxis(
+                self.u32()? as i32,
+                Axis::from_string(self.name()?)?,
+            )),
+            1 => Some(PhysicalInput::CAxisButton(
+                self.u32()? as i32,
+                Axis::from_string(self.name()?)?,
+                self.bool()?,
+            )),
+            2 => Some(PhysicalInput::CButton(
+                self.u32()? as i32,
+                Button::from_string(self.name()?)?,
+            )),
+            3 => Some(PhysicalInput::MButton(mouse_button_from_name(
+                self.name()?,
+            )?)),
+            4 => Some(PhysicalInput::MWheelX(self.bool()?)),
+            5 => Some(PhysicalInput::MWheelY(self.bool()?)),
+            6 => Some(PhysicalInput::MMotion),
+            7 => Some(PhysicalInput::Key(Keycode::from_name(self.name()?)?)),
+            _ => None,
+        }
+    }
+
+    fn value(&mut self) -> Option<PhysicalInputValue> {
+        match self.u8()? {
+            0 => Some(PhysicalInputValue::Axis(self.u16()? as i16)),
+            1 => Some(PhysicalInputValue::Button(self.bool()?)),
+            2 => Some(PhysicalInputValue::Repeat),
+            3 => Some(PhysicalInputValue::XY(
+                self.u32()? as i32,
+                self.u32()? as i32,
+                self.u32()? as i32,
+                self.u32()? as i32,
+            )),
+            4 => Some(PhysicalInputValue::Wheel(self.u32()? as i32)),
+            _ => None,
+        }
+    }
+
+    fn event(&mut self) -> Option<RecordedEvent> {
+        Some(RecordedEvent {
+            frame: self.u32()?,
+            timestamp: f32::from_bits(self.u32()?),
+            keymod: Mod::from_bits_truncate(self.u16()?),
+            physical: self.physical()?,
+            value: self.value()?,
+        })
+    }
+}
+
+fn read_binary(bytes: &[u8]) -> Result<Recording, InputtyError> {
+    let mut reader = BinaryReader { bytes };
+    let version = check_version(reader.u16())?;
+    let mut recording = Recording::new();
+    if version >= 2 {
+        let frame_ends = reader
+            .u32()
+            .and_then(|count| {
+                (0..count)
+                    .map(|_| reader.u32().map(f32::from_bits))
+                    .collect()
+            })
+            .ok_or_else(|| InputtyError::new("Malformed recording frame ends"))?;
+        recording.frame_ends = frame_ends;
+    }
+    while !reader.bytes.is_empty() {
+        match reader.event() {
+            Some(event) => recording.events.push(event),
+            None => {
+                return Err(InputtyError::new(format!(
+                    "Malformed recording at event {}",
+                    recording.events.len()
+                )))
+            }
+        }
+    }
+    Ok(recording)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use ggez::event::MouseButton;
+
+    fn sample() -> Recording {
+        let events = vec![
+            (
+                PhysicalInput::Key(Keycode::LShift),
+                PhysicalInputValue::Button(true),
+            ),
+            (
+                PhysicalInput::CAxisButton(0, Axis::LeftX, false),
+                PhysicalInputValue::Button(false),
+            ),
+            (
+                PhysicalInput::CButton(1, Button::DPadLeft),
+                PhysicalInputValue::Button(true),
+            ),
+            (
+                PhysicalInput::CAxis(0, Axis::TriggerRight),
+                PhysicalInputValue::Axis(-16384),
+            ),
+            (
+                PhysicalInput::MButton(MouseButton::X1),
+                PhysicalInputValue::Button(false),
+            ),
+            (PhysicalInput::MWheelY(true), PhysicalInputValue::Wheel(-2)),
+            (
+                PhysicalInput::MMotion,
+                PhysicalInputValue::XY(320, 240, -4, 2),
+            ),
+            (PhysicalInput::Key(Keycode::A), PhysicalInputValue::Repeat),
+        ];
+        Recording {
+            events: events
+                .into_iter()
+                .enumerate()
+                .map(|(index, (physical, value))| RecordedEvent {
+                    frame: index as u32 / 2,
+                    timestamp: index as f32 / 60.0,
+                    physical,
+                    value,
+                    keymod: Mod::LSHIFTMOD,
+                })
+                .collect(),
+            frame_ends: (0..5).map(|frame| (frame * 2 + 1) as f32 / 60.0).collect(),
+        }
+    }
+
+    #[test]
+    fn text_round_trip() {
+        let recording = sample();
+        let mut text = Vec::new();
+        recording.write_text(&mut text).unwrap();
+        assert!(text.starts_with(b"inputty-recording 2\n"));
+        assert_eq!(Recording::read(&text[..]).unwrap(), recording);
+    }
+
+    #[test]
+    fn binary_round_trip() {
+        let recording = sample();
+        let mut bytes = Vec::new();
+        recording.write_binary(&mut bytes).unwrap();
+        assert_eq!(Recording::read(&bytes[..]).unwrap(), recording);
+        bytes.pop();
+        assert!(Recording::read(&bytes[..]).is_err());
+    }
+
+    #[test]
+    fn unsupported_version() {
+        assert!(Recording::read(&b"inputty-recording 3\n"[..]).is_err());
+        assert!(Recording::read(&b"INPT\x03\x00"[..]).is_err());
+        let version_1 = Recording::read(&b"INPT\x01\x00"[..]).unwrap();
+        assert_eq!(version_1, Recording::new());
+        assert!(Recording::read(&b"something else\n"[..]).is_err());
+    }
+}
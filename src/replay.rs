@@ -0,0 +1,347 @@
+//! Deterministic replay of recordings, without a window or SDL2.
+//!
+//! A `Replay` feeds a `Recording` to an `InputHandler` through `InputHandler::replay_event()`,
+//! either a frame at a time, or by elapsed time scaled by a speed multiplier. Before each event,
+//! and at the end of each recorded frame, handler time is advanced with `InputHandler::update()`
+//! to where it was at that point of the recording, relative to when the replay started, so
+//! time-driven interactions and sequences play out the same. Replaying the same recording into
+//! a handler and state set up the same way always invokes the same callbacks with the same
+//! values, which lets tests assert on the state afterwards.
+
+use error::{DispatchResult, InputtyError};
+use input_handler::InputHandler;
+use recording::{RecordedEvent, Recording};
+use std::fmt::Debug;
+use std::hash::Hash;
+
+/// Playback position in a recording.
+pub struct Replay {
+    recording: Recording,
+    /// Index of the next event to dispatch.
+    next: usize,
+    /// Frame `step_frame()` dispatches next.
+    frame: u32,
+    /// Seconds since the start of the recording.
+    time: f32,
+    speed: f32,
+    /// Handler time when the replay started.
+    origin: Option<f32>,
+}
+
+impl Replay {
+    pub fn new(recording: Recording) -> Self {
+        Replay {
+            recording,
+            next: 0,
+            frame: 0,
+            time: 0.0,
+            speed: 1.0,
+            origin: None,
+        }
+    }
+
+    /// Sets how many seconds of the recording pass per second given to `advance()`.
+    pub fn with_speed(mut self, speed: f32) -> Self {
+        self.speed = speed;
+        self
+    }
+
+    pub fn set_speed(&mut self, speed: f32) -> &mut Self {
+        self.speed = speed;
+        self
+    }
+
+    pub fn speed(&self) -> f32 {
+        self.speed
+    }
+
+    pub fn frame(&self) -> u32 {
+        self.frame
+    }
+
+    /// Seconds since the start of the recording.
+    pub fn time(&self) -> f32 {
+        self.time
+    }
+
+    pub fn is_finished(&self) -> bool {
+        self.next >= self.recording.events.len()
+            && self.frame as usize >= self.recording.frame_ends.len()
+    }
+
+    pub fn recording(&self) -> &Recording {
+        &self.recording
+    }
+
+    /// Checks that every logical input the recorded physical inputs can reach in given handler
+    /// is defined; undefined ones would be silently skipped by the replay.
+    pub fn check<LogicalInput, State>(
+        &self,
+        handler: &InputHandler<LogicalInput, State>,
+    ) -> Result<(), InputtyError>
+    where
+        LogicalInput: Hash + Eq + Clone + Debug,
+    {
+        let mut physicals = Vec::new();
+        for event in &self.recording.events {
+            if !physicals.contains(&event.physical) {
+                physicals.push(event.physical);
+            }
+        }
+        let undefined = handler.undefined_logicals(&physicals);
+        if undefined.is_empty() {
+            Ok(())
+        } else {
+            Err(InputtyError::new(format!(
+                "Recording reaches undefined logical inputs: {:?}",
+                undefined
+            )))
+        }
+    }
+
+    /// Dispatches the rest of the current frame's events, advances handler time to where the
+    /// frame ended, and moves on to the next frame.
+    pub fn step_frame<LogicalInput, State>(
+        &mut self,
+        handler: &mut InputHandler<LogicalInput, State>,
+        state: &mut State,
+    ) -> DispatchResult<LogicalInput>
+    where
+        LogicalInput: Hash + Eq + Clone + Debug,
+    {
+        let frame = self.frame;
+        self.dispatch_while(handler, state, |event| event.frame <= frame)?;
+        self.end_frame(handler, state, frame)?;
+        self.frame = frame + 1;
+        Ok(())
+    }
+
+    /// Moves playback forward by given seconds, scaled by speed, dispatching events on the way;
+    /// handler time is advanced along.
+    pub fn advance<LogicalInput, State>(
+        &mut self,
+        handler: &mut InputHandler<LogicalInput, State>,
+        state: &mut State,
+        delta_time: f32,
+    ) -> DispatchResult<LogicalInput>
+    where
+        LogicalInput: Hash + Eq + Clone + Debug,
+    {
+        let time = self.time + delta_time * self.speed;
+        self.dispatch_while(handler, state, |event| event.timestamp <= time)?;
+        self.time = time;
+        self.catch_up(handler, state, time)?;
+        while let Some(&end) = self.recording.frame_ends.get(self.frame as usize) {
+            if end > time {
+                break;
+            }
+            self.frame += 1;
+        }
+        Ok(())
+    }
+
+    /// Dispatches events up to given frame, so that `step_frame()` dispatches it next. Handler
+    /// and state can't be rewound, so seeking backwards restarts the replay: it should then be
+    /// given a fresh handler and state, set up the same way as the original ones.
+    pub fn seek_to_frame<LogicalInput, State>(
+        &mut self,
+        handler: &mut InputHandler<LogicalInput, State>,
+        state: &mut State,
+        frame: u32,
+    ) -> DispatchResult<LogicalInput>
+    where
+        LogicalInput: Hash + Eq + Clone + Debug,
+    {
+        if frame < self.frame {
+            self.rewind();
+        }
+        self.dispatch_while(handler, state, |event| event.frame < frame)?;
+        if frame > 0 {
+            self.end_frame(handler, state, frame - 1)?;
+        }
+        self.frame = frame;
+        Ok(())
+    }
+
+    /// Restarts the replay from the beginning of the recording.
+    pub fn rewind(&mut self) -> &mut Self {
+        self.next = 0;
+        self.frame = 0;
+        self.time = 0.0;
+        self.origin = None;
+        self
+    }
+
+    fn dispatch_while<LogicalInput, State, F>(
+        &mut self,
+        handler: &mut InputHandler<LogicalInput, State>,
+        state: &mut State,
+        condition: F,
+    ) -> DispatchResult<LogicalInput>
+    where
+        LogicalInput: Hash + Eq + Clone + Debug,
+        F: Fn(&RecordedEvent) -> bool,
+    {
+        while let Some(event) = self.recording.events.get(self.next).cloned() {
+            if !condition(&event) {
+                break;
+            }
+            self.catch_up(handler, state, event.timestamp)?;
+            self.next += 1;
+            if event.timestamp > self.time {
+                self.time = event.timestamp;
+            }
+            if event.frame > self.frame {
+                self.frame = event.frame;
+            }
+            handler.replay_event(state, &event)?;
+        }
+        Ok(())
+    }
+
+    /// Advances handler time to where given frame ended, if the recording notes it.
+    fn end_frame<LogicalInput, State>(
+        &mut self,
+        handler: &mut InputHandler<LogicalInput, State>,
+        state: &mut State,
+        frame: u32,
+    ) -> DispatchResult<LogicalInput>
+    where
+        LogicalInput: Hash + Eq + Clone + Debug,
+    {
+        if let Some(&end) = self.recording.frame_ends.get(frame as usize) {
+            self.catch_up(handler, state, end)?;
+            if end > self.time {
+                self.time = end;
+            }
+        }
+        Ok(())
+    }
+
+    /// Advances handler time to where it was at given recording time.
+    fn catch_up<LogicalInput, State>(
+        &mut self,
+        handler: &mut InputHandler<LogicalInput, State>,
+        state: &mut State,
+        time: f32,
+    ) -> DispatchResult<LogicalInput>
+    where
+        LogicalInput: Hash + Eq + Clone + Debug,
+    {
+        let origin = *self.origin.get_or_insert(handler.time());
+        let delta_time = origin + time - handler.time();
+        if delta_time > 0.0 {
+            handler.update(state, delta_time)?;
+        }
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use ggez::event::{Keycode, Mod};
+    use input_handler::{PhysicalInput, PhysicalInputValue};
+    use interaction::Interaction;
+    use options::BindingOptions;
+
+    type Log = Vec<(u32, PhysicalInputValue)>;
+
+    fn setup() -> InputHandler<u32, Log> {
+        let mut handler = InputHandler::<u32, Log>::new();
+        for &logical in &[0, 1] {
+            handler.define(logical, move |log, _, value| {
+                log.push((logical, value));
+                Ok(())
+            });
+        }
+        handler
+            .bind(PhysicalInput::Key(Keycode::Space), 0)
+            .bind_with(
+                PhysicalInput::Key(Keycode::Return),
+                1,
+                BindingOptions::new().interaction(Interaction::Hold { duration: 0.45 }),
+            );
+        handler
+    }
+
+    /// Records a session; also returns how long the log was at the end of each frame.
+    fn session(handler: &mut InputHandler<u32, Log>, log: &mut Log) -> (Recording, Vec<usize>) {
+        let mut lengths = Vec::new();
+        handler.set_time(10.0);
+        handler.start_recording();
+        for frame in 0..10 {
+            match frame {
+                1 => handler.key_down_event(log, Keycode::Space, Mod::empty(), false),
+                2 => handler.key_up_event(log, Keycode::Space, Mod::empty(), false),
+                3 => handler.key_down_event(log, Keycode::Return, Mod::empty(), false),
+                9 => handler.key_up_event(log, Keycode::Return, Mod::empty(), false),
+                _ => Ok(()),
+            }
+            .unwrap();
+            handler.update(log, 0.1).unwrap();
+            lengths.push(log.len());
+        }
+        (handler.stop_recording().unwrap(), lengths)
+    }
+
+    #[test]
+    fn replay_matches_session() {
+        let mut log = Log::new();
+        let (recording, lengths) = session(&mut setup(), &mut log);
+        assert_eq!(lengths, vec![0, 1, 2, 2, 2, 2, 2, 3, 3, 4]);
+
+        let mut replayed = Log::new();
+        let mut handler = setup();
+        let mut replay = Replay::new(recording.clone());
+        replay.check(&handler).unwrap();
+        for &length in &lengths {
+            replay.step_frame(&mut handler, &mut replayed).unwrap();
+            assert_eq!(replayed, log[..length].to_vec());
+        }
+        assert!(replay.is_finished());
+
+        let mut replayed = Log::new();
+        let mut handler = setup();
+        let mut replay = Replay::new(recording).with_speed(2.0);
+        replay.advance(&mut handler, &mut replayed, 0.125).unwrap();
+        assert_eq!(replay.time(), 0.25);
+        assert_eq!(replayed, log[..2].to_vec());
+        replay.advance(&mut handler, &mut replayed, 1.0).unwrap();
+        assert!(replay.is_finished());
+        assert_eq!(replayed, log);
+    }
+
+    #[test]
+    fn seeking() {
+        let mut log = Log::new();
+        let (recording, _) = session(&mut setup(), &mut log);
+        let mut replayed = Log::new();
+        let mut handler = setup();
+        let mut replay = Replay::new(recording);
+        replay
+            .seek_to_frame(&mut handler, &mut replayed, 3)
+            .unwrap();
+        assert_eq!(replay.frame(), 3);
+        assert_eq!(replayed, log[..2].to_vec());
+
+        let mut replayed = Log::new();
+        let mut handler = setup();
+        replay
+            .seek_to_frame(&mut handler, &mut replayed, 2)
+            .unwrap();
+        assert_eq!(replayed, log[..1].to_vec());
+    }
+
+    #[test]
+    fn undefined_logicals() {
+        let mut log = Log::new();
+        let (recording, _) = session(&mut setup(), &mut log);
+        let mut handler = setup();
+        handler.bind(PhysicalInput::Key(Keycode::Return), 2);
+        assert!(Replay::new(recording.clone()).check(&handler).is_err());
+        handler.bind(PhysicalInput::Key(Keycode::Escape), 3);
+        handler.define(2, |_, _, _| Ok(()));
+        assert!(Replay::new(recording).check(&handler).is_ok());
+    }
+}
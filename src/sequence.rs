@@ -0,0 +1,121 @@
+use input_handler::PhysicalInput;
+
+/// A single step of an input sequence; it's matched by a press.
+#[derive(PartialEq, Clone, Debug)]
+pub enum SequenceStep<LogicalInput> {
+    Physical(PhysicalInput),
+    Logical(LogicalInput),
+}
+
+/// Timing of an input sequence, in seconds of `InputHandler` time.
+#[derive(PartialEq, Clone, Debug)]
+pub struct SequenceOptions {
+    /// Longest time allowed between two consecutive steps.
+    pub max_gap: f32,
+    /// Longest time allowed between the first and the last steps.
+    pub window: f32,
+    /// Whether presses that don't match the next step are skipped instead of breaking the
+    /// sequence.
+    pub allow_interleaved: bool,
+}
+
+impl SequenceOptions {
+    pub fn new() -> Self {
+        SequenceOptions {
+            max_gap: 0.5,
+            window: 2.0,
+            allow_interleaved: false,
+        }
+    }
+
+    pub fn max_gap(mut self, max_gap: f32) -> Self {
+        self.max_gap = max_gap;
+        self
+    }
+
+    pub fn window(mut self, window: f32) -> Self {
+        self.window = window;
+        self
+    }
+
+    pub fn allow_interleaved(mut self, allow_interleaved: bool) -> Self {
+        self.allow_interleaved = allow_interleaved;
+        self
+    }
+}
+
+impl Default for SequenceOptions {
+    fn default() -> Self {
+        SequenceOptions::new()
+    }
+}
+
+/// A sequence that was started, but not completed yet.
+struct PartialMatch {
+    started: f32,
+    last: f32,
+    next: usize,
+}
+
+pub(crate) struct Sequence<LogicalInput> {
+    pub steps: Vec<SequenceStep<LogicalInput>>,
+    pub logical: LogicalInput,
+    pub options: SequenceOptions,
+    partials: Vec<PartialMatch>,
+}
+
+impl<LogicalInput: PartialEq> Sequence<LogicalInput> {
+    pub fn new(
+        steps: Vec<SequenceStep<LogicalInput>>,
+        logical: LogicalInput,
+        options: SequenceOptions,
+    ) -> Self {
+        Sequence {
+            steps,
+            logical,
+            options,
+            partials: Vec::new(),
+        }
+    }
+
+    /// Advances the sequence with a press of given physical input, which pressed given logical
+    /// inputs, at given time; returns `true` if the sequence was just completed.
+    pub fn press(&mut self, physical: PhysicalInput, logicals: &[LogicalInput], now: f32) -> bool {
+        if self.steps.is_empty() {
+            return false;
+        }
+        let matches = |step: &SequenceStep<LogicalInput>| match *step {
+            SequenceStep::Physical(ref expected) => *expected == physical,
+            SequenceStep::Logical(ref expected) => logicals.contains(expected),
+        };
+        let options = &self.options;
+        self.partials.retain(|partial| {
+            now - partial.last <= options.max_gap && now - partial.started <= options.window
+        });
+
+        let mut completed = false;
+        let steps = &self.steps;
+        self.partials
+            .retain(|partial| options.allow_interleaved || matches(&steps[partial.next]));
+        for partial in &mut self.partials {
+            if matches(&steps[partial.next]) {
+                partial.next += 1;
+                partial.last = now;
+                completed |= partial.next == steps.len();
+            }
+        }
+        if matches(&steps[0]) {
+            completed |= steps.len() == 1;
+            self.partials.push(PartialMatch {
+                started: now,
+                last: now,
+                next: 1,
+            });
+        }
+
+        if completed {
+            self.partials.clear();
+        }
+        completed
+    }
+}
@@ -1,7 +1,9 @@
 use ggez::nalgebra;
 use input_handler::{InputHandler, InputHandlerDefGen, InputtyResult, PhysicalInputValue};
+use options::AxisNormalization;
 use std::fmt::Debug;
 use std::hash::Hash;
+use std::rc::Rc;
 
 #[derive(Hash, PartialEq, Eq, Clone, Debug)]
 pub enum VirtualAxisInput {
@@ -18,12 +20,113 @@ pub enum VirtualAxisPhase {
     Ignore,
 }
 
+/// Decides which direction of a virtual axis wins when both are held.
+#[derive(PartialEq, Copy, Clone, Debug)]
+pub enum AxisConflictPolicy {
+    /// Direction pressed last wins; releasing it relaxes the axis.
+    LastPressed,
+    /// Direction pressed while the other one is active is ignored; releasing the active one
+    /// relaxes the axis.
+    FirstPressed,
+    /// Axis relaxes while both are held; releasing one resumes the other.
+    Cancel,
+    /// Direction pressed last wins; releasing it resumes the other one, if it's still held.
+    ResumeHeld,
+}
+
+/// Maps analog axis magnitude, past dead zones and rescaled to 0..1, to output magnitude.
+#[derive(PartialEq, Clone, Debug)]
+pub enum ResponseCurve {
+    Linear,
+    /// Magnitude raised to given power; above 1.0 gives finer control near the center.
+    Power(f32),
+    /// Output magnitudes sampled at evenly spaced points from 0.0 to 1.0, inclusive;
+    /// interpolated linearly.
+    Lookup(Vec<f32>),
+}
+
+impl ResponseCurve {
+    pub fn apply(&self, magnitude: f32) -> f32 {
+        match *self {
+            ResponseCurve::Linear => magnitude,
+            ResponseCurve::Power(exponent) => magnitude.powf(exponent),
+            ResponseCurve::Lookup(ref samples) => {
+                if samples.len() < 2 {
+                    return samples.first().cloned().unwrap_or(magnitude);
+                }
+                let position = magnitude * (samples.len() - 1) as f32;
+                let index = (position.floor() as usize).min(samples.len() - 2);
+                let fraction = position - index as f32;
+                samples[index] + (samples[index + 1] - samples[index]) * fraction
+            }
+        }
+    }
+}
+
+/// How relative mouse motion drives a virtual axis through its analog input.
+#[derive(PartialEq, Clone, Debug)]
+pub struct MouseAxis {
+    /// Whether horizontal motion is used, instead of vertical.
+    pub horizontal: bool,
+    /// Axis value per pixel of motion, before acceleration.
+    pub sensitivity: f32,
+    pub inverted: bool,
+    /// Maps magnitude of the motion accumulated during a frame, scaled by sensitivity and
+    /// clamped to 0..1, to axis magnitude.
+    pub acceleration: ResponseCurve,
+}
+
+impl MouseAxis {
+    pub fn x(sensitivity: f32) -> Self {
+        MouseAxis {
+            horizontal: true,
+            sensitivity,
+            inverted: false,
+            acceleration: ResponseCurve::Linear,
+        }
+    }
+
+    pub fn y(sensitivity: f32) -> Self {
+        MouseAxis {
+            horizontal: false,
+            ..MouseAxis::x(sensitivity)
+        }
+    }
+
+    pub fn inverted(mut self, inverted: bool) -> Self {
+        self.inverted = inverted;
+        self
+    }
+
+    pub fn acceleration(mut self, acceleration: ResponseCurve) -> Self {
+        self.acceleration = acceleration;
+        self
+    }
+}
+
 pub struct VirtualAxisState {
     value: f32,
     phase: VirtualAxisPhase,
     delta: f32,
     delta_reverse: f32,
     delta_relax: f32,
+    dead_zone: f32,
+    outer_dead_zone: f32,
+    response_curve: ResponseCurve,
+    normalization: AxisNormalization,
+    conflict_policy: AxisConflictPolicy,
+    /// Number of physical inputs holding the positive direction.
+    pos_held: u32,
+    /// Number of physical inputs holding the negative direction.
+    neg_held: u32,
+    mouse: Option<MouseAxis>,
+    /// Relative mouse motion since the last update, in pixels.
+    mouse_motion: i32,
+    /// Smoothing time, in seconds.
+    smoothing: Option<f32>,
+    /// Analog value the smoothed value is pulled towards.
+    target: f32,
+    velocity: f32,
 }
 
 impl VirtualAxisState {
@@ -34,33 +137,230 @@ impl VirtualAxisState {
             delta,
             delta_reverse,
             delta_relax,
+            dead_zone: 0.0,
+            outer_dead_zone: 1.0,
+            response_curve: ResponseCurve::Linear,
+            normalization: AxisNormalization::bipolar(),
+            conflict_policy: AxisConflictPolicy::LastPressed,
+            pos_held: 0,
+            neg_held: 0,
+            mouse: None,
+            mouse_motion: 0,
+            smoothing: None,
+            target: 0.0,
+            velocity: 0.0,
         }
     }
 
+    /// Creates a virtual axis that takes `rise` seconds to go from rest to full deflection,
+    /// `reverse` seconds to get back to rest from full opposite deflection, and `relax` seconds
+    /// to return to rest from full deflection once released; zero means instant.
+    pub fn from_durations(rise: f32, reverse: f32, relax: f32) -> Self {
+        let rate = |duration: f32| {
+            if duration > 0.0 {
+                1.0 / duration
+            } else {
+                f32::INFINITY
+            }
+        };
+        VirtualAxisState::new(rate(rise), rate(reverse), rate(relax))
+    }
+
+    /// Sets analog input magnitudes below `inner` to read as 0.0, and above `outer` as 1.0.
+    pub fn with_dead_zone(mut self, inner: f32, outer: f32) -> Self {
+        self.dead_zone = inner;
+        self.outer_dead_zone = outer;
+        self
+    }
+
+    /// Sets the curve analog input is shaped with, after dead zones.
+    pub fn with_response_curve(mut self, response_curve: ResponseCurve) -> Self {
+        self.response_curve = response_curve;
+        self
+    }
+
+    /// Sets how raw analog input is mapped, before dead zones.
+    pub fn with_normalization(mut self, normalization: AxisNormalization) -> Self {
+        self.normalization = normalization;
+        self
+    }
+
+    /// Makes analog input accept relative mouse motion, accumulated until the next update.
+    pub fn with_mouse(mut self, mouse: MouseAxis) -> Self {
+        self.mouse = Some(mouse);
+        self
+    }
+
+    /// Makes the value follow input like a critically damped spring, settling in roughly
+    /// `smoothing` seconds, instead of changing at constant rates; digital input then pulls it
+    /// straight to full deflection or rest.
+    pub fn with_smoothing(mut self, smoothing: f32) -> Self {
+        self.smoothing = Some(smoothing);
+        self
+    }
+
+    /// Sets how simultaneously held positive and negative directions are resolved.
+    pub fn with_conflict_policy(mut self, conflict_policy: AxisConflictPolicy) -> Self {
+        self.conflict_policy = conflict_policy;
+        self
+    }
+
     pub fn value(&self) -> f32 {
         self.value
     }
 
+    /// Advances the value by `delta_time` seconds; the result doesn't depend on how the time
+    /// is split between calls, as long as input doesn't change in between.
     pub fn update(&mut self, delta_time: f32) {
-        axis_update(
-            &mut self.value,
-            &self.phase,
-            delta_time * self.delta,
-            delta_time * self.delta_reverse,
-            delta_time * self.delta_relax,
-        );
+        if delta_time <= 0.0 {
+            return;
+        }
+        match self.smoothing {
+            None => {
+                if self.phase == VirtualAxisPhase::Ignore {
+                    // Mouse motion consumed by the previous update returns to rest.
+                    self.value = self.target;
+                }
+                axis_update(
+                    &mut self.value,
+                    &self.phase,
+                    delta_time * self.delta,
+                    delta_time * self.delta_reverse,
+                    delta_time * self.delta_relax,
+                );
+                self.target = self.value;
+            }
+            Some(smoothing) => {
+                self.target = match self.phase {
+                    VirtualAxisPhase::Increase => 1.0,
+                    VirtualAxisPhase::Decrease => -1.0,
+                    VirtualAxisPhase::Relax => 0.0,
+                    VirtualAxisPhase::Ignore => self.target,
+                };
+                axis_smooth(
+                    &mut self.value,
+                    &mut self.velocity,
+                    self.target,
+                    smoothing,
+                    delta_time,
+                );
+            }
+        }
+        if self.mouse_motion != 0 {
+            self.mouse_motion = 0;
+            self.target = 0.0;
+        }
     }
 
+    fn set_analog(&mut self, value: f32) {
+        self.target = value;
+        if self.smoothing.is_none() {
+            self.value = value;
+        }
+    }
+
+    /// Takes raw controller axis values, or relative mouse motion if set up with `with_mouse()`;
+    /// the latter is consumed by the next update, and the value returns to rest on the one
+    /// after, unless there's more motion by then.
     pub fn input_analog(&mut self, value: PhysicalInputValue) -> InputtyResult {
-        axis_input_analog(&mut self.value, &mut self.phase, value)
+        match value {
+            PhysicalInputValue::Axis(raw_axis) => {
+                self.phase = VirtualAxisPhase::Ignore;
+                self.mouse_motion = 0;
+                let value = axis_shape(
+                    self.normalization.normalize(raw_axis),
+                    self.dead_zone,
+                    self.outer_dead_zone,
+                    &self.response_curve,
+                );
+                self.set_analog(value);
+            }
+            PhysicalInputValue::XY(_, _, xrel, yrel) => {
+                if let Some(ref mouse) = self.mouse {
+                    self.phase = VirtualAxisPhase::Ignore;
+                    self.mouse_motion += if mouse.horizontal { xrel } else { yrel };
+                    let motion = self.mouse_motion as f32 * mouse.sensitivity;
+                    let magnitude = nalgebra::clamp(motion.abs(), 0.0, 1.0);
+                    let value = mouse.acceleration.apply(magnitude) * motion.signum();
+                    let value = if mouse.inverted { -value } else { value };
+                    self.set_analog(value);
+                }
+            }
+            _ => (),
+        }
+        Ok(())
     }
 
     pub fn input_pos(&mut self, value: PhysicalInputValue) -> InputtyResult {
-        axis_input_pos(&mut self.phase, value)
+        self.input_direction(true, value)
     }
 
     pub fn input_neg(&mut self, value: PhysicalInputValue) -> InputtyResult {
-        axis_input_neg(&mut self.phase, value)
+        self.input_direction(false, value)
+    }
+
+    fn input_direction(&mut self, positive: bool, value: PhysicalInputValue) -> InputtyResult {
+        let down = match value {
+            PhysicalInputValue::Button(raw_button) => raw_button,
+            _ => return Ok(()),
+        };
+        let (this_held, other_held, this_phase, other_phase) = if positive {
+            (
+                &mut self.pos_held,
+                self.neg_held > 0,
+                VirtualAxisPhase::Increase,
+                VirtualAxisPhase::Decrease,
+            )
+        } else {
+            (
+                &mut self.neg_held,
+                self.pos_held > 0,
+                VirtualAxisPhase::Decrease,
+                VirtualAxisPhase::Increase,
+            )
+        };
+        if down {
+            *this_held += 1;
+        } else {
+            if *this_held == 0 {
+                return Ok(());
+            }
+            *this_held -= 1;
+            // Another input still holds this direction.
+            if *this_held > 0 {
+                return Ok(());
+            }
+        }
+        if down {
+            match self.conflict_policy {
+                AxisConflictPolicy::LastPressed | AxisConflictPolicy::ResumeHeld => {
+                    self.phase = this_phase;
+                }
+                AxisConflictPolicy::FirstPressed => {
+                    if self.phase != other_phase {
+                        self.phase = this_phase;
+                    }
+                }
+                AxisConflictPolicy::Cancel => {
+                    self.phase = if other_held {
+                        VirtualAxisPhase::Relax
+                    } else {
+                        this_phase
+                    };
+                }
+            }
+        } else {
+            let resume = match self.conflict_policy {
+                AxisConflictPolicy::LastPressed | AxisConflictPolicy::FirstPressed => false,
+                AxisConflictPolicy::Cancel | AxisConflictPolicy::ResumeHeld => true,
+            };
+            if resume && other_held {
+                self.phase = other_phase;
+            } else if self.phase != other_phase {
+                self.phase = VirtualAxisPhase::Relax;
+            }
+        }
+        Ok(())
     }
 }
 
@@ -85,18 +385,18 @@ macro_rules! define_virtual_axis {
     };
 }
 
-/*impl<
-    LogicalInput,
-    State,
-> InputHandlerDefGen<
-    LogicalInput,
-    State,
-    (LogicalInput, LogicalInput, LogicalInput),
-    VirtualAxisState,
-> for InputHandler<LogicalInput, State>
+impl<LogicalInput, State>
+    InputHandlerDefGen<
+        LogicalInput,
+        State,
+        (LogicalInput, LogicalInput, LogicalInput),
+        VirtualAxisState,
+    > for InputHandler<LogicalInput, State>
 where
     LogicalInput: Hash + Eq + Clone + Debug,
 {
+    /// Defines analog, positive and negative logical inputs, in that order, of the virtual axis
+    /// `state_extractor` finds in the state.
     fn define_exp<F>(
         &mut self,
         logical: (LogicalInput, LogicalInput, LogicalInput),
@@ -105,16 +405,19 @@ where
     where
         F: 'static + Fn(&mut State) -> &mut VirtualAxisState,
     {
+        let state_extractor = Rc::new(state_extractor);
+        let extractor = state_extractor.clone();
         self.define(
             logical.0,
             move |_state, _physical, _value| -> InputtyResult {
-                state_extractor(_state).input_analog(_value)
+                extractor(_state).input_analog(_value)
             },
         );
+        let extractor = state_extractor.clone();
         self.define(
             logical.1,
             move |_state, _physical, _value| -> InputtyResult {
-                state_extractor(_state).input_pos(_value)
+                extractor(_state).input_pos(_value)
             },
         );
         self.define(
@@ -124,7 +427,7 @@ where
             },
         )
     }
-}*/
+}
 
 pub fn axis_update(
     axis_value: &mut f32,
@@ -144,16 +447,21 @@ pub fn axis_update(
                 val = 0.0;
             }
         }
+        // Part of the step left after returning to rest goes at the normal rate.
         VirtualAxisPhase::Increase => {
-            if val > 0.0 {
+            if val >= 0.0 {
                 val += delta;
+            } else if -val < delta_reverse {
+                val = (1.0 + val / delta_reverse) * delta;
             } else {
                 val += delta_reverse;
             }
         }
         VirtualAxisPhase::Decrease => {
-            if val < 0.0 {
+            if val <= 0.0 {
                 val -= delta;
+            } else if val < delta_reverse {
+                val = -(1.0 - val / delta_reverse) * delta;
             } else {
                 val -= delta_reverse;
             }
@@ -163,6 +471,41 @@ pub fn axis_update(
     *axis_value = nalgebra::clamp(val, -1.0, 1.0);
 }
 
+/// Moves `value` towards `target` over `delta_time` seconds, as a critically damped spring
+/// settling in roughly `smoothing` seconds; exact for any step length.
+pub fn axis_smooth(
+    value: &mut f32,
+    velocity: &mut f32,
+    target: f32,
+    smoothing: f32,
+    delta_time: f32,
+) {
+    if smoothing <= 0.0 {
+        *value = target;
+        *velocity = 0.0;
+        return;
+    }
+    let omega = 2.0 / smoothing;
+    let offset = *value - target;
+    let slope = *velocity + omega * offset;
+    let decay = (-omega * delta_time).exp();
+    *value = target + (offset + slope * delta_time) * decay;
+    *velocity = (slope - omega * (offset + slope * delta_time)) * decay;
+}
+
+/// Applies dead zones and a response curve to a -1.0..1.0 analog value; magnitude between
+/// `inner` and `outer` is rescaled to 0.0..1.0 before going through the curve.
+pub fn axis_shape(value: f32, inner: f32, outer: f32, response_curve: &ResponseCurve) -> f32 {
+    let magnitude = if outer > inner {
+        nalgebra::clamp((value.abs() - inner) / (outer - inner), 0.0, 1.0)
+    } else if value.abs() > inner {
+        1.0
+    } else {
+        0.0
+    };
+    nalgebra::clamp(response_curve.apply(magnitude), 0.0, 1.0) * value.signum()
+}
+
 pub fn axis_input_analog(
     axis_value: &mut f32,
     axis_state: &mut VirtualAxisPhase,
@@ -170,7 +513,7 @@ pub fn axis_input_analog(
 ) -> InputtyResult {
     if let PhysicalInputValue::Axis(raw_axis) = value {
         *axis_state = VirtualAxisPhase::Ignore;
-        *axis_value = raw_axis as f32 / i16::max_value() as f32;
+        *axis_value = (raw_axis as f32 / i16::max_value() as f32).max(-1.0);
     }
     Ok(())
 }
@@ -202,3 +545,231 @@ pub fn axis_input_neg(
     }
     Ok(())
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn dead_zones() {
+        let linear = ResponseCurve::Linear;
+        assert_eq!(axis_shape(0.1, 0.2, 0.9, &linear), 0.0);
+        assert_eq!(axis_shape(-0.2, 0.2, 0.9, &linear), 0.0);
+        assert!((axis_shape(0.55, 0.2, 0.9, &linear) - 0.5).abs() < 1e-6);
+        assert_eq!(axis_shape(-0.95, 0.2, 0.9, &linear), -1.0);
+    }
+
+    #[test]
+    fn response_curves() {
+        assert!((ResponseCurve::Power(2.0).apply(0.5) - 0.25).abs() < 1e-6);
+        let lookup = ResponseCurve::Lookup(vec![0.0, 0.2, 1.0]);
+        assert!((lookup.apply(0.25) - 0.1).abs() < 1e-6);
+        assert!((lookup.apply(0.75) - 0.6).abs() < 1e-6);
+        assert_eq!(lookup.apply(1.0), 1.0);
+    }
+
+    /// Feeds space-separated events, like `+ -^` for "press positive, release negative", and
+    /// checks phase after each against `I`ncrease, `D`ecrease or `R`elax.
+    fn check_conflicts(policy: AxisConflictPolicy, events: &str, expected: &str) {
+        let mut axis = VirtualAxisState::new(0.1, 0.1, 0.1).with_conflict_policy(policy);
+        for (event, phase) in events.split(' ').zip(expected.split(' ')) {
+            let value = PhysicalInputValue::Button(!event.ends_with('^'));
+            if event.starts_with('+') {
+                axis.input_pos(value).unwrap();
+            } else {
+                axis.input_neg(value).unwrap();
+            }
+            let expected_phase = match phase {
+                "I" => VirtualAxisPhase::Increase,
+                "D" => VirtualAxisPhase::Decrease,
+                _ => VirtualAxisPhase::Relax,
+            };
+            assert_eq!(
+                axis.phase, expected_phase,
+                "{:?}: {} after {} in {}",
+                policy, phase, event, events
+            );
+        }
+    }
+
+    #[test]
+    fn conflict_policies() {
+        use self::AxisConflictPolicy::*;
+        let orderings = [
+            "+ - +^ -^",
+            "+ - -^ +^",
+            "- + +^ -^",
+            "- + -^ +^",
+            "+ +^ - -^",
+            "- -^ + +^",
+            "+ - +^ +",
+            "+ + - +^ -^ +^",
+        ];
+        let cases = [
+            (
+                LastPressed,
+                "I D D R | I D R R | D I R R | D I I R | I R D R | D R I R | I D D I | I I D D R R",
+            ),
+            (
+                FirstPressed,
+                "I I R R | I I I R | D D D R | D D R R | I R D R | D R I R | I I R I | I I I I I R",
+            ),
+            (
+                Cancel,
+                "I R D R | I R I R | D R D R | D R I R | I R D R | D R I R | I R D R | I I R R I R",
+            ),
+            (
+                ResumeHeld,
+                "I D D R | I D I R | D I D R | D I I R | I R D R | D R I R | I D D I | I I D D I R",
+            ),
+        ];
+        for &(policy, expected) in cases.iter() {
+            for (events, expected) in orderings.iter().zip(expected.split(" | ")) {
+                check_conflicts(policy, events, expected);
+            }
+        }
+    }
+
+    #[test]
+    fn define_exp() {
+        use ggez::event::{Keycode, Mod};
+        use input_handler::PhysicalInput;
+
+        let mut players = vec![
+            VirtualAxisState::new(0.5, 0.5, 0.5),
+            VirtualAxisState::new(0.5, 0.5, 0.5),
+        ];
+        let mut handler = InputHandler::<(usize, VirtualAxisInput), Vec<_>>::new();
+        for player in 0..players.len() {
+            handler.define_exp(
+                (
+                    (player, VirtualAxisInput::Analog),
+                    (player, VirtualAxisInput::Positive),
+                    (player, VirtualAxisInput::Negative),
+                ),
+                move |players: &mut Vec<VirtualAxisState>| &mut players[player],
+            );
+        }
+        handler
+            .bind(
+                PhysicalInput::Key(Keycode::S),
+                (0, VirtualAxisInput::Positive),
+            )
+            .bind(
+                PhysicalInput::Key(Keycode::Up),
+                (1, VirtualAxisInput::Negative),
+            );
+
+        handler.key_down_event(&mut players, Keycode::S, Mod::empty(), false).unwrap();
+        handler.key_down_event(&mut players, Keycode::Up, Mod::empty(), false).unwrap();
+        for axis in &mut players {
+            axis.update(1.0);
+        }
+        assert_eq!(players[0].value(), 0.5);
+        assert_eq!(players[1].value(), -0.5);
+    }
+
+    #[test]
+    fn normalization() {
+        let bipolar = AxisNormalization::bipolar();
+        assert_eq!(bipolar.normalize(i16::min_value()), -1.0);
+        assert_eq!(bipolar.normalize(0), 0.0);
+        assert_eq!(bipolar.normalize(i16::max_value()), 1.0);
+        assert_eq!(bipolar.inverted(true).normalize(i16::max_value()), -1.0);
+
+        let trigger = AxisNormalization::trigger();
+        assert_eq!(trigger.normalize(-100), 0.0);
+        assert_eq!(trigger.normalize(i16::max_value()), 1.0);
+        assert_eq!(trigger.inverted(true).normalize(0), 1.0);
+
+        let calibrated = AxisNormalization::calibrated(-20000, 1000, 21000);
+        assert_eq!(calibrated.normalize(1000), 0.0);
+        assert_eq!(calibrated.normalize(-30000), -1.0);
+        assert_eq!(calibrated.normalize(11000), 0.5);
+        assert_eq!(calibrated.apply(-9500), -i16::max_value() / 2 - 1);
+
+        let mut axis = VirtualAxisState::new(0.1, 0.1, 0.1).with_normalization(trigger);
+        axis.input_analog(PhysicalInputValue::Axis(i16::max_value() / 2))
+            .unwrap();
+        assert!((axis.value() - 0.5).abs() < 1e-4);
+    }
+
+    #[test]
+    fn mouse_motion() {
+        let mut axis = VirtualAxisState::new(0.1, 0.1, 0.1).with_mouse(
+            MouseAxis::y(0.01)
+                .inverted(true)
+                .acceleration(ResponseCurve::Power(2.0)),
+        );
+        axis.input_analog(PhysicalInputValue::XY(0, 0, 100, 20))
+            .unwrap();
+        axis.input_analog(PhysicalInputValue::XY(0, 0, 100, 30))
+            .unwrap();
+        assert!((axis.value() + 0.25).abs() < 1e-6);
+        axis.update(1.0);
+        assert!((axis.value() + 0.25).abs() < 1e-6);
+        axis.update(1.0);
+        assert_eq!(axis.value(), 0.0);
+        axis.input_analog(PhysicalInputValue::XY(0, 0, 0, 20))
+            .unwrap();
+        axis.update(1.0);
+        axis.input_analog(PhysicalInputValue::XY(0, 0, 0, 30))
+            .unwrap();
+        assert!((axis.value() + 0.09).abs() < 1e-6);
+        axis.update(1.0);
+        assert!((axis.value() + 0.09).abs() < 1e-6);
+        axis.update(1.0);
+        assert_eq!(axis.value(), 0.0);
+        axis.input_analog(PhysicalInputValue::XY(0, 0, 0, -500))
+            .unwrap();
+        assert_eq!(axis.value(), 1.0);
+
+        let mut axis = VirtualAxisState::new(0.1, 0.1, 0.1);
+        axis.input_analog(PhysicalInputValue::XY(0, 0, 100, 100))
+            .unwrap();
+        assert_eq!(axis.value(), 0.0);
+    }
+
+    #[test]
+    fn step_invariance() {
+        let configs: [fn() -> VirtualAxisState; 3] = [
+            || VirtualAxisState::from_durations(0.2, 0.05, 0.1),
+            || VirtualAxisState::from_durations(0.2, 0.0, 0.1),
+            || VirtualAxisState::from_durations(0.2, 0.05, 0.1).with_smoothing(0.1),
+        ];
+        for config in configs.iter() {
+            let mut values = Vec::new();
+            for &steps in &[1, 10, 7] {
+                let mut axis = config();
+                axis.input_neg(PhysicalInputValue::Button(true)).unwrap();
+                for _ in 0..steps {
+                    axis.update(0.03 / steps as f32);
+                }
+                axis.input_neg(PhysicalInputValue::Button(false)).unwrap();
+                axis.input_pos(PhysicalInputValue::Button(true)).unwrap();
+                for _ in 0..steps {
+                    axis.update(0.1 / steps as f32);
+                }
+                values.push(axis.value());
+            }
+            for value in &values {
+                assert!((value - values[0]).abs() < 1e-5, "{:?}", values);
+            }
+        }
+        let mut axis = VirtualAxisState::from_durations(0.2, 0.05, 0.1);
+        axis.input_pos(PhysicalInputValue::Button(true)).unwrap();
+        axis.update(0.1);
+        assert!((axis.value() - 0.5).abs() < 1e-6);
+    }
+
+    #[test]
+    fn analog_only() {
+        let mut axis = VirtualAxisState::new(0.5, 0.5, 0.5).with_dead_zone(0.25, 1.0);
+        axis.input_analog(PhysicalInputValue::Axis(i16::max_value() / 8))
+            .unwrap();
+        assert_eq!(axis.value(), 0.0);
+        axis.input_pos(PhysicalInputValue::Button(true)).unwrap();
+        axis.update(0.2);
+        assert!((axis.value() - 0.1).abs() < 1e-6);
+    }
+}
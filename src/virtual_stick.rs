@@ -0,0 +1,429 @@
+use ggez::nalgebra;
+use input_handler::{InputHandler, InputHandlerDefGen, InputtyResult, PhysicalInputValue};
+use std::f32::consts::PI;
+use std::fmt::Debug;
+use std::hash::Hash;
+use std::rc::Rc;
+use virtual_axis::{axis_shape, ResponseCurve};
+
+#[derive(Hash, PartialEq, Eq, Clone, Debug)]
+pub enum VirtualStickInput {
+    AnalogX,
+    AnalogY,
+    Up,
+    Down,
+    Left,
+    Right,
+}
+
+/// How dead zones are applied to analog input of a virtual stick.
+#[derive(PartialEq, Copy, Clone, Debug)]
+pub enum StickDeadZone {
+    /// Applied to the length of the vector; doesn't distort direction.
+    Radial,
+    /// Applied to each axis separately; makes it easier to hold exactly horizontal or vertical.
+    Axial,
+}
+
+/// Restriction of a virtual stick's direction.
+#[derive(PartialEq, Copy, Clone, Debug)]
+pub enum StickSnap {
+    None,
+    /// Up, down, left and right.
+    Four,
+    /// Same as `Four`, plus diagonals.
+    Eight,
+}
+
+/// Two-dimensional counterpart of `VirtualAxisState`; axes follow SDL2, positive Y is down.
+pub struct VirtualStickState {
+    analog: (f32, f32),
+    /// Numbers of physical inputs holding each digital direction.
+    up: u32,
+    down: u32,
+    left: u32,
+    right: u32,
+    dead_zone_kind: StickDeadZone,
+    dead_zone: f32,
+    outer_dead_zone: f32,
+    snap: StickSnap,
+}
+
+impl VirtualStickState {
+    pub fn new() -> Self {
+        VirtualStickState {
+            analog: (0.0, 0.0),
+            up: 0,
+            down: 0,
+            left: 0,
+            right: 0,
+            dead_zone_kind: StickDeadZone::Radial,
+            dead_zone: 0.0,
+            outer_dead_zone: 1.0,
+            snap: StickSnap::None,
+        }
+    }
+
+    /// Sets analog input magnitudes below `inner` to read as 0.0, and above `outer` as 1.0.
+    pub fn with_dead_zone(mut self, kind: StickDeadZone, inner: f32, outer: f32) -> Self {
+        self.dead_zone_kind = kind;
+        self.dead_zone = inner;
+        self.outer_dead_zone = outer;
+        self
+    }
+
+    pub fn with_snap(mut self, snap: StickSnap) -> Self {
+        self.snap = snap;
+        self
+    }
+
+    /// Current direction, no longer than 1.0; digital directions take precedence over analog
+    /// input while any of them are held.
+    pub fn value(&self) -> (f32, f32) {
+        let digital = (
+            (self.right > 0) as i32 as f32 - (self.left > 0) as i32 as f32,
+            (self.down > 0) as i32 as f32 - (self.up > 0) as i32 as f32,
+        );
+        let (x, y) = if digital != (0.0, 0.0) {
+            digital
+        } else {
+            self.shaped_analog()
+        };
+        let (x, y) = clamp_length((x, y));
+        match self.snap {
+            StickSnap::None => (x, y),
+            StickSnap::Four => snap_direction((x, y), 4),
+            StickSnap::Eight => snap_direction((x, y), 8),
+        }
+    }
+
+    pub fn input_analog_x(&mut self, value: PhysicalInputValue) -> InputtyResult {
+        if let PhysicalInputValue::Axis(raw_axis) = value {
+            self.analog.0 = normalize_raw(raw_axis);
+        }
+        Ok(())
+    }
+
+    pub fn input_analog_y(&mut self, value: PhysicalInputValue) -> InputtyResult {
+        if let PhysicalInputValue::Axis(raw_axis) = value {
+            self.analog.1 = normalize_raw(raw_axis);
+        }
+        Ok(())
+    }
+
+    pub fn input_up(&mut self, value: PhysicalInputValue) -> InputtyResult {
+        stick_input_digital(&mut self.up, value)
+    }
+
+    pub fn input_down(&mut self, value: PhysicalInputValue) -> InputtyResult {
+        stick_input_digital(&mut self.down, value)
+    }
+
+    pub fn input_left(&mut self, value: PhysicalInputValue) -> InputtyResult {
+        stick_input_digital(&mut self.left, value)
+    }
+
+    pub fn input_right(&mut self, value: PhysicalInputValue) -> InputtyResult {
+        stick_input_digital(&mut self.right, value)
+    }
+
+    fn shaped_analog(&self) -> (f32, f32) {
+        let (inner, outer) = (self.dead_zone, self.outer_dead_zone);
+        match self.dead_zone_kind {
+            StickDeadZone::Radial => {
+                let length = (self.analog.0 * self.analog.0 + self.analog.1 * self.analog.1).sqrt();
+                if length <= inner || length == 0.0 {
+                    return (0.0, 0.0);
+                }
+                let scaled = if outer > inner {
+                    nalgebra::clamp((length - inner) / (outer - inner), 0.0, 1.0)
+                } else {
+                    1.0
+                };
+                (
+                    self.analog.0 / length * scaled,
+                    self.analog.1 / length * scaled,
+                )
+            }
+            StickDeadZone::Axial => (
+                axis_shape(self.analog.0, inner, outer, &ResponseCurve::Linear),
+                axis_shape(self.analog.1, inner, outer, &ResponseCurve::Linear),
+            ),
+        }
+    }
+}
+
+impl Default for VirtualStickState {
+    fn default() -> Self {
+        VirtualStickState::new()
+    }
+}
+
+#[macro_export]
+macro_rules! define_virtual_stick {
+    ($handler:ident, $logical:path, $state:ident) => {
+        $handler.define(
+            $logical(VirtualStickInput::AnalogX),
+            |_state, _physical, _value| -> InputtyResult {
+                _state.$state.input_analog_x(_value)
+            });
+        $handler.define(
+            $logical(VirtualStickInput::AnalogY),
+            |_state, _physical, _value| -> InputtyResult {
+                _state.$state.input_analog_y(_value)
+            });
+        $handler.define(
+            $logical(VirtualStickInput::Up),
+            |_state, _physical, _value| -> InputtyResult {
+                _state.$state.input_up(_value)
+            });
+        $handler.define(
+            $logical(VirtualStickInput::Down),
+            |_state, _physical, _value| -> InputtyResult {
+                _state.$state.input_down(_value)
+            });
+        $handler.define(
+            $logical(VirtualStickInput::Left),
+            |_state, _physical, _value| -> InputtyResult {
+                _state.$state.input_left(_value)
+            });
+        $handler.define(
+            $logical(VirtualStickInput::Right),
+            |_state, _physical, _value| -> InputtyResult {
+                _state.$state.input_right(_value)
+            });
+    };
+}
+
+impl<LogicalInput, State>
+    InputHandlerDefGen<
+        LogicalInput,
+        State,
+        (
+            LogicalInput,
+            LogicalInput,
+            LogicalInput,
+            LogicalInput,
+            LogicalInput,
+            LogicalInput,
+        ),
+        VirtualStickState,
+    > for InputHandler<LogicalInput, State>
+where
+    LogicalInput: Hash + Eq + Clone + Debug,
+{
+    /// Defines analog X, analog Y, up, down, left and right logical inputs, in that order, of
+    /// the virtual stick `state_extractor` finds in the state.
+    fn define_exp<F>(
+        &mut self,
+        logical: (
+            LogicalInput,
+            LogicalInput,
+            LogicalInput,
+            LogicalInput,
+            LogicalInput,
+            LogicalInput,
+        ),
+        state_extractor: F,
+    ) -> &mut InputHandler<LogicalInput, State>
+    where
+        F: 'static + Fn(&mut State) -> &mut VirtualStickState,
+    {
+        let state_extractor = Rc::new(state_extractor);
+        let extractor = state_extractor.clone();
+        self.define(
+            logical.0,
+            move |_state, _physical, _value| -> InputtyResult {
+                extractor(_state).input_analog_x(_value)
+            },
+        );
+        let extractor = state_extractor.clone();
+        self.define(
+            logical.1,
+            move |_state, _physical, _value| -> InputtyResult {
+                extractor(_state).input_analog_y(_value)
+            },
+        );
+        let extractor = state_extractor.clone();
+        self.define(
+            logical.2,
+            move |_state, _physical, _value| -> InputtyResult {
+                extractor(_state).input_up(_value)
+            },
+        );
+        let extractor = state_extractor.clone();
+        self.define(
+            logical.3,
+            move |_state, _physical, _value| -> InputtyResult {
+                extractor(_state).input_down(_value)
+            },
+        );
+        let extractor = state_extractor.clone();
+        self.define(
+            logical.4,
+            move |_state, _physical, _value| -> InputtyResult {
+                extractor(_state).input_left(_value)
+            },
+        );
+        self.define(
+            logical.5,
+            move |_state, _physical, _value| -> InputtyResult {
+                state_extractor(_state).input_right(_value)
+            },
+        )
+    }
+}
+
+/// Counts physical inputs holding a digital direction.
+pub fn stick_input_digital(held: &mut u32, value: PhysicalInputValue) -> InputtyResult {
+    match value {
+        PhysicalInputValue::Button(true) => *held += 1,
+        PhysicalInputValue::Button(false) if *held > 0 => *held -= 1,
+        _ => (),
+    }
+    Ok(())
+}
+
+fn normalize_raw(raw_axis: i16) -> f32 {
+    nalgebra::clamp(raw_axis as f32 / i16::max_value() as f32, -1.0, 1.0)
+}
+
+fn clamp_length((x, y): (f32, f32)) -> (f32, f32) {
+    let length = (x * x + y * y).sqrt();
+    if length > 1.0 {
+        (x / length, y / length)
+    } else {
+        (x, y)
+    }
+}
+
+fn snap_direction((x, y): (f32, f32), directions: u32) -> (f32, f32) {
+    let length = (x * x + y * y).sqrt();
+    if length == 0.0 {
+        return (0.0, 0.0);
+    }
+    let sector = 2.0 * PI / directions as f32;
+    let angle = (y.atan2(x) / sector).round() * sector;
+    (angle.cos() * length, angle.sin() * length)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn assert_close((x, y): (f32, f32), (expected_x, expected_y): (f32, f32)) {
+        assert!(
+            (x - expected_x).abs() < 1e-4 && (y - expected_y).abs() < 1e-4,
+            "({}, {}) != ({}, {})",
+            x,
+            y,
+            expected_x,
+            expected_y
+        );
+    }
+
+    #[test]
+    fn digital_diagonal() {
+        let mut stick = VirtualStickState::new();
+        stick.input_up(PhysicalInputValue::Button(true)).unwrap();
+        stick.input_right(PhysicalInputValue::Button(true)).unwrap();
+        let half = 0.5f32.sqrt();
+        assert_close(stick.value(), (half, -half));
+        stick.input_up(PhysicalInputValue::Button(false)).unwrap();
+        assert_close(stick.value(), (1.0, 0.0));
+    }
+
+    #[test]
+    fn radial_dead_zone() {
+        let mut stick = VirtualStickState::new().with_dead_zone(StickDeadZone::Radial, 0.2, 1.0);
+        let raw = (0.1 * i16::max_value() as f32) as i16;
+        stick.input_analog_x(PhysicalInputValue::Axis(raw)).unwrap();
+        stick.input_analog_y(PhysicalInputValue::Axis(raw)).unwrap();
+        assert_close(stick.value(), (0.0, 0.0));
+        stick
+            .input_analog_x(PhysicalInputValue::Axis(i16::max_value()))
+            .unwrap();
+        stick
+            .input_analog_y(PhysicalInputValue::Axis(i16::max_value()))
+            .unwrap();
+        let half = 0.5f32.sqrt();
+        assert_close(stick.value(), (half, half));
+    }
+
+    #[test]
+    fn snapping() {
+        let mut stick = VirtualStickState::new().with_snap(StickSnap::Four);
+        stick
+            .input_analog_x(PhysicalInputValue::Axis(i16::max_value() / 2))
+            .unwrap();
+        stick
+            .input_analog_y(PhysicalInputValue::Axis(i16::max_value() / 4))
+            .unwrap();
+        let length = (0.5f32 * 0.5 + 0.25 * 0.25).sqrt();
+        assert_close(stick.value(), (length, 0.0));
+
+        let mut stick = VirtualStickState::new().with_snap(StickSnap::Eight);
+        stick
+            .input_analog_x(PhysicalInputValue::Axis(i16::max_value() / 2))
+            .unwrap();
+        stick
+            .input_analog_y(PhysicalInputValue::Axis(i16::max_value() / 4))
+            .unwrap();
+        let half = 0.5f32.sqrt();
+        assert_close(stick.value(), (length * half, length * half));
+    }
+
+    #[test]
+    fn axial_dead_zone() {
+        let mut stick = VirtualStickState::new().with_dead_zone(StickDeadZone::Axial, 0.2, 0.9);
+        let raw = |value: f32| PhysicalInputValue::Axis((value * i16::max_value() as f32) as i16);
+        stick.input_analog_x(raw(0.55)).unwrap();
+        stick.input_analog_y(raw(-0.1)).unwrap();
+        assert_close(stick.value(), (0.5, 0.0));
+        stick.input_analog_y(raw(-1.0)).unwrap();
+        assert_close(stick.value(), (0.5 / 1.25f32.sqrt(), -1.0 / 1.25f32.sqrt()));
+    }
+
+    #[test]
+    fn define_virtual_stick() {
+        use ggez::event::{Button, Keycode, Mod};
+        use input_handler::PhysicalInput;
+
+        #[derive(Hash, PartialEq, Eq, Clone, Debug)]
+        enum Input {
+            Move(VirtualStickInput),
+        }
+        struct Player {
+            stick: VirtualStickState,
+        }
+
+        let mut handler = InputHandler::<Input, Player>::new();
+        define_virtual_stick!(handler, Input::Move, stick);
+        handler
+            .bind(
+                PhysicalInput::Key(Keycode::D),
+                Input::Move(VirtualStickInput::Right),
+            )
+            .bind(
+                PhysicalInput::CButton(0, Button::DPadRight),
+                Input::Move(VirtualStickInput::Right),
+            );
+        let mut player = Player {
+            stick: VirtualStickState::new(),
+        };
+        handler
+            .key_down_event(&mut player, Keycode::D, Mod::empty(), false)
+            .unwrap();
+        handler
+            .controller_button_down_event(&mut player, Button::DPadRight, 0)
+            .unwrap();
+        handler
+            .key_up_event(&mut player, Keycode::D, Mod::empty(), false)
+            .unwrap();
+        assert_close(player.stick.value(), (1.0, 0.0));
+        handler
+            .controller_button_up_event(&mut player, Button::DPadRight, 0)
+            .unwrap();
+        assert_close(player.stick.value(), (0.0, 0.0));
+    }
+}